@@ -185,7 +185,7 @@ async function main() {
     );
 
     const [planChunk] = PublicKey.findProgramAddressSync(
-      [Buffer.from("plans"), vestingAccount.toBuffer()],
+      [Buffer.from("plans"), vestingAccount.toBuffer(), Buffer.from([0, 0])], // page 0 (u16 LE)
       programId
     );
 
@@ -232,6 +232,8 @@ async function main() {
         startTime: vestingTime,
         endTime: endTime,
         category: category,
+        kind: { plan: {} },
+        cliffTime: new BN(0),
       })
       .accountsPartial({
         admin: schedulerAdminPubkey,
@@ -92,7 +92,7 @@ async function main() {
     const tokenVaultPubkey = new PublicKey("7ockDYD8ERbK23LBWVWjgduRibJuwnkJ2Kqs2e4xteTB");
         
     // --- Todo: Configuration ---
-    const parentVaultPubkey = new PublicKey("A1o6S2DW5CUE5hg91bauqEb1gWG2vnHiEVT7QUGtCXTA");
+    const parentVaultPubkey = new PublicKey("A1o6S2DW5CUE5hg91bauqEb1gWG2vnHiEVT7QUGtCXTA");  // beneficiary_vault of the parent vesting below
     const parentVestingAccountPubkey = new PublicKey("DqbiNzBoXft4VKzoxvc7fgs8tChbLzd6q6DmASeeHuVu");
     const oldUserPlan = new PublicKey("B2LHQoJS7RbeqG1nfNL7HTYS3XoFYSwX77q9J1oE5wsr");
     
@@ -196,12 +196,12 @@ async function main() {
     );
 
     const [parentPlanChunk] = PublicKey.findProgramAddressSync(
-      [Buffer.from("plans"), parentVestingAccountPubkey.toBuffer()],
+      [Buffer.from("plans"), parentVestingAccountPubkey.toBuffer(), Buffer.from([0, 0])], // page 0 (u16 LE)
       programId
     );
         
     const [planChunk] = PublicKey.findProgramAddressSync(
-      [Buffer.from("plans"), vestingAccount.toBuffer()],
+      [Buffer.from("plans"), vestingAccount.toBuffer(), Buffer.from([0, 0])], // page 0 (u16 LE)
       programId
     );
 
@@ -241,6 +241,8 @@ async function main() {
       startTime: new BN(startTimestamp),
       endTime: new BN(endTimestamp),
       category,
+      kind: { plan: {} },                     // Child vestings are always plan-based
+      cliffTime: new BN(0),                   // No cliff
     };
 
     console.log("- Total Amount:", totalAmount.toString());
@@ -298,6 +300,10 @@ async function main() {
     console.log("\n=== Step 2: Appending Yearly Plans ===");
     
     const chunkSize = 80;
+    const [allocation] = PublicKey.findProgramAddressSync(
+      [Buffer.from("allocation"), vestingAccount.toBuffer()],
+      programId
+    );
     let isValidPlanChunkInfo = await connection.getAccountInfo(planChunk);
     
     if (!isValidPlanChunkInfo) {
@@ -312,20 +318,29 @@ async function main() {
         releaseTime: p.releaseTime,
         amount: p.amount,
         released: p.released,
+        releasedAmount: p.released ? p.amount : new BN(0),
       }));
 
       console.log(`Appending ${plansForTx.length} plans in chunks of ${chunkSize}...`);
       
       for (let i = 0; i < plansForTx.length; i += chunkSize) {
         const chunk = plansForTx.slice(i, i + chunkSize);
-        console.log(`- Adding chunk ${Math.floor(i / chunkSize) + 1}: ${chunk.length} plans`);
+        const pageIndex = Math.floor(i / chunkSize); // one plan chunk page per slice
+        console.log(`- Adding chunk ${pageIndex + 1}: ${chunk.length} plans`);
+
+        const [pagePlanChunk] = PublicKey.findProgramAddressSync(
+          [Buffer.from("plans"), vestingAccount.toBuffer(), new BN(pageIndex).toArrayLike(Buffer, "le", 2)],
+          programId
+        );
 
         const ix = await program.methods
-          .appendYearlyPlan(chunk)
+          .appendYearlyPlan(pageIndex, chunk, { incremental: {} })
           .accountsPartial({
             vestingAccount: vestingAccount,
-            planChunk: planChunk,
+            planChunk: pagePlanChunk,
             parentPlanChunk: parentPlanChunk,
+            parentVestingAccount: parentVestingAccountPubkey, // Its plans are deducted too
+            allocation: allocation, // Child vestings record which parent plans fund them
             admin: schedulerAdminPubkey,
             adminConfig: adminConfig,
             systemProgram: SystemProgram.programId,
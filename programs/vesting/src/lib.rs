@@ -1,6 +1,7 @@
 use anchor_lang::prelude::*;                          // Anchor basic prelude: Import accounts, macros, and types
+use anchor_lang::system_program;                      // System program CPI (rent top-up on migration)
 use anchor_spl::associated_token::get_associated_token_address; // SPL ATA utility: Function for calculating ATA
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer}; // Types/functions used for SPL Token CPI
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer}; // Types/functions used for SPL Token CPI
 
 declare_id!("DcjmKSSKNxbSAwBQZx8wSAhosxBxQoyz3DdXuysMiPTy"); // Declare program ID (on-chain program id)
 
@@ -8,6 +9,8 @@ const CATEGORY_MAX_LEN: usize = 50;                   // Maximum length for the
 const DISCRIMINATOR_SIZE: usize = 8;                  // Anchor account discriminator (8 bytes)
 const STRING_LENGTH_PREFIX: usize = 4; // String length prefix (u32) - Anchor prepends this during String serialization
 
+const BATCH_ENTRY_ACCOUNTS: usize = 3;                // Fixed accounts per batch_do_vesting entry, before its plan storage and allowlist
+
 const VESTING_ACCOUNT_SPACE: usize = DISCRIMINATOR_SIZE
     + 32  // beneficiary (Pubkey)
     + 8   // total_amount
@@ -21,7 +24,51 @@ const VESTING_ACCOUNT_SPACE: usize = DISCRIMINATOR_SIZE
     + STRING_LENGTH_PREFIX + CATEGORY_MAX_LEN  // category (String)
     + 1   // is_active (bool)
     + 32 // destination_token_account (Pubkey)
-    + 32; // parent_vault - calculate total account space
+    + 32  // parent_vault
+    + 1   // kind (VestingKind)
+    + 8   // cliff_time
+    + 1   // destination_policy (DestinationPolicy)
+    + 2;  // plan_chunk_count (u16) - calculate total account space
+
+const YEARLY_PLAN_SPACE: usize = 8 + 8 + 1 + 8;        // release_time + amount + released + released_amount
+const LEGACY_YEARLY_PLAN_SPACE: usize = 8 + 8 + 1;    // YearlyPlan before partial releases
+const MAX_PLANS: usize = 128;                         // Plans one chunk page may grow to
+const LEGACY_MAX_PLANS: usize = 52;                   // Fixed capacity of legacy chunks
+const LEGACY_PLAN_CHUNK_SPACE: usize = DISCRIMINATOR_SIZE + 32 + 4 + LEGACY_MAX_PLANS * LEGACY_YEARLY_PLAN_SPACE;
+
+// Chunks are sized to their plans and reallocated as plans are added or removed
+const fn plan_chunk_space(plan_count: usize) -> usize {
+    DISCRIMINATOR_SIZE + 32 + 4 + plan_count * YEARLY_PLAN_SPACE // vesting_account + Vec<YearlyPlan>
+}
+
+// Layout written before kind/cliff_time/destination_policy/plan_chunk_count existed; migrate_vesting_account upgrades these accounts
+const LEGACY_VESTING_ACCOUNT_SPACE: usize = VESTING_ACCOUNT_SPACE - 1 - 8 - 1 - 2;
+
+const ZERO_COPY_MAX_PLANS: usize = 256;               // Fixed capacity of a zero-copy plan chunk
+const ZERO_COPY_PLAN_CHUNK_SPACE: usize = DISCRIMINATOR_SIZE + std::mem::size_of::<ZeroCopyPlanChunk>();
+
+const TIP_POOL_SPACE: usize = DISCRIMINATOR_SIZE + 32 + 8; // token_mint + tip_lamports
+
+const ALLOCATION_ENTRY_SPACE: usize = 8 + 8 + 32;     // release_time + amount + parent_plan_chunk
+
+// Allocation ledgers grow by one entry per funded child plan
+const fn plan_allocation_space(entry_count: usize) -> usize {
+    DISCRIMINATOR_SIZE + 32 + 32 + 4 + entry_count * ALLOCATION_ENTRY_SPACE // parent_vesting + child_vesting + Vec<AllocationEntry>
+}
+
+const ADMIN_CONFIG_SPACE: usize = DISCRIMINATOR_SIZE + 32 + 32 + 32; // admin + pending_admin + seed_admin
+const LEGACY_ADMIN_CONFIG_SPACE: usize = DISCRIMINATOR_SIZE + 32;    // AdminConfig before admin handover existed
+
+const PLAN_TIMELOCK_SPACE: usize = DISCRIMINATOR_SIZE + 8 + 8 + 8; // delay + pending_delay + pending_delay_at
+
+const MAX_APPROVERS: usize = 10;                      // Keys in an M-of-N signer set
+const APPROVAL_CONFIG_SPACE: usize = DISCRIMINATOR_SIZE + 4 + MAX_APPROVERS * 32 + 1 + 8; // signers + threshold + proposal_count
+
+const MAX_ROLE_MEMBERS: usize = 4;                    // Keys per role
+const ROLES_SPACE: usize = DISCRIMINATOR_SIZE + 5 * (4 + MAX_ROLE_MEMBERS * 32); // Five Vec<Pubkey>
+
+const MAX_DESTINATIONS: usize = 8;                    // Allowlisted destination token accounts per vesting
+const DESTINATION_ALLOWLIST_SPACE: usize = DISCRIMINATOR_SIZE + 32 + 4 + MAX_DESTINATIONS * 32; // vesting_account + Vec<Pubkey>
 
 #[program]
 pub mod vesting {                                      // Start of the Anchor program module
@@ -33,6 +80,25 @@ pub mod vesting {                                      // Start of the Anchor pr
         Ok(())
     }
 
+    // Hand the deployer role to another key (e.g. a multisig); signed by the current deployer
+    pub fn rotate_deployer(ctx: Context<RotateDeployer>, new_deployer: Pubkey) -> Result<()> {
+        require!(new_deployer != Pubkey::default(), VestingError::InvalidParameters);
+        consume_approval(
+            &ctx.accounts.approval_config,
+            ctx.accounts.proposal.as_deref_mut(),
+            &ProposalOperation::RotateDeployer { new_deployer },
+        )?;
+        let deploy_admin = &mut ctx.accounts.deploy_admin;
+        let previous_deployer = deploy_admin.deployer;
+        deploy_admin.deployer = new_deployer;
+
+        emit!(DeployerRotated {
+            previous_deployer,
+            new_deployer,
+        });
+        Ok(())
+    }
+
     // Set admin account
     pub fn initialize(ctx: Context<Initialize>) -> Result<()> { // Deployer designates an admin
         require!(
@@ -40,7 +106,11 @@ pub mod vesting {                                      // Start of the Anchor pr
             VestingError::NotDeployAdmin
         );
         let admin_config = &mut ctx.accounts.admin_config; // Reference to the AdminConfig PDA
+        require!(admin_config.admin == Pubkey::default(), VestingError::AdminAlreadySet); // New, or cleared by remove_admin
         admin_config.admin = ctx.accounts.admin.key();      // Designate as admin
+        if admin_config.seed_admin == Pubkey::default() {
+            admin_config.seed_admin = ctx.accounts.admin.key(); // Vault/token_info PDAs stay derived from the first admin
+        }
         Ok(())
     }
 
@@ -52,17 +122,11 @@ pub mod vesting {                                      // Start of the Anchor pr
     ) -> Result<()> {
         let now = Clock::get()?;                          // Current on-chain time
         let vesting_account = &mut ctx.accounts.vesting_account; // Target vesting account
-        let admin = &ctx.accounts.admin;                   // Admin account (signer)
-        let admin_config = &ctx.accounts.admin_config;     // Admin configuration
 
         // Debug logs commented out
         // msg!("vesting_account = {}", vesting_account.key());
         // ...
 
-        require!(
-            admin_config.admin == admin.key(),            // Does the call signer match the registered admin?
-            VestingError::Unauthorized
-        );
         require!(vesting_account.is_active, VestingError::NotActive); // Is the vesting active?
         require!(
             vesting_account.last_release_time <= now.unix_timestamp,  // Has time passed since the last release?
@@ -80,16 +144,11 @@ pub mod vesting {                                      // Start of the Anchor pr
             ctx.accounts.token_mint.key(),
             VestingError::InvalidMint
         );
-        require_keys_eq!(
-            ctx.accounts.plan_chunk.vesting_account,
-            vesting_account.key(),
-            VestingError::InvalidParameters
-        );
-
         require!(
             vesting_time <= now.unix_timestamp,           // Is the requested release time in the past or present?
             VestingError::VestingNotReached
         );
+        vesting_account.check_cliff(now.unix_timestamp)?; // Nothing is releasable before the cliff
         // token_vault, parent_vault
         // Verify origin_token_account PDA
         let expected_origin_pda = Pubkey::find_program_address( // Calculate the expected source Vault PDA
@@ -109,82 +168,282 @@ pub mod vesting {                                      // Start of the Anchor pr
         );
 
         // Verify destination_token_address ATA
-        let expected_ata = get_associated_token_address(       // Standard ATA for the beneficiary
-            &ctx.accounts.beneficiary.key(),
-            &ctx.accounts.token_mint.key(),
+        check_destination(
+            &ctx.accounts.destination_token_account,
+            vesting_account,
+            &ctx.accounts.token_info.mint_wallet_address,
+            ctx.accounts.destination_allowlist.as_deref(),
+        )?;
+        let destination = ctx.accounts.destination_token_account.key();
+        if destination != get_associated_token_address(&vesting_account.beneficiary, &vesting_account.token_mint) {
+            consume_approval(                                  // Paying anyone but the beneficiary is a drain
+                &ctx.accounts.approval_config,
+                ctx.accounts.proposal.as_deref_mut(),
+                &ProposalOperation::Release {
+                    vesting_account: vesting_account.key(),
+                    vesting_time,
+                    amount,
+                    destination,
+                },
+            )?;
+        }
+
+        let release_amount = match vesting_account.kind {
+            VestingKind::Plan => {
+                // The backend finds the plan chunk corresponding to the vesting_account, and among them, finds the plan where release_time == vesting_time
+                if let Some(vesting_plan) = ctx.accounts.plan_chunk.as_mut() { // Collection of plans for this vesting (PDA)
+                    require_keys_eq!(
+                        vesting_plan.vesting_account,
+                        vesting_account.key(),
+                        VestingError::InvalidParameters
+                    );
+                    let plan = find_matured_plan(&mut vesting_plan.plans, vesting_time, now.unix_timestamp)?; // Exactly one matured, unreleased plan
+                    plan.release(amount)?;                     // Full or partial; marked released once fully paid
+                } else {
+                    // Zero-copy storage: only the matching slot is read and written
+                    let zero_copy_plans = ctx
+                        .accounts
+                        .zero_copy_plans
+                        .as_ref()
+                        .ok_or(VestingError::InvalidParameters)?;
+                    let mut zero_copy_plans = zero_copy_plans.load_mut()?;
+                    let slot = zero_copy_plans
+                        .plans_mut()
+                        .iter_mut()
+                        .find(|p| p.release_time == vesting_time)
+                        .ok_or(VestingError::InvalidParameters)?;
+                    let mut plan = YearlyPlan::from(&*slot);
+                    find_matured_plan(std::slice::from_mut(&mut plan), vesting_time, now.unix_timestamp)?.release(amount)?;
+                    *slot = ZeroCopyPlan::from(&plan);
+                }
+                amount
+            }
+            VestingKind::Linear => {
+                let releasable = vesting_account.releasable_linear_amount(now.unix_timestamp)?; // Unlocked by the clock
+                require!(releasable > 0, VestingError::NoTokensToRelease);
+                require!(amount > 0 && amount <= releasable, VestingError::InvalidParameters); // Cannot run ahead of the schedule
+                amount
+            }
+        };
+
+        let admin_key = ctx.accounts.admin_config.seed_admin;  // Vault authority is derived from the first admin, not the scheduler
+        let token_vault_key = ctx.accounts.token_vault.key();  // Token vault key
+
+        let (_vault_authority_pda, bump) = Pubkey::find_program_address( // Calculate the vault authority PDA
+            &[b"vault_auth", admin_key.as_ref(), token_vault_key.as_ref()],
+            ctx.program_id,
         );
+        transfer_from_vault(                                   // SPL Token transfer CPI signed by the vault_auth PDA
+            ctx.accounts.token_program.to_account_info(),      // Token program
+            ctx.accounts.origin_token_account.to_account_info(), // Source
+            ctx.accounts.destination_token_account.to_account_info(), // Destination
+            ctx.accounts.vault_authority.to_account_info(),    // Authority (PDA)
+            &admin_key,
+            &token_vault_key,
+            bump,
+            release_amount,                                    // Transfer only what this call releases
+        )?;
+
+        vesting_account.record_release(release_amount, now.unix_timestamp)?; // Update cumulative released amount and last release time
+
+        Ok(())
+    }
+
+    // Release every plan that matured up to now, e.g. after scheduler downtime
+    // remaining_accounts: every plan chunk page of the vesting, or its zero-copy chunk once migrated
+    pub fn catch_up_vesting<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CatchUpVesting<'info>>,
+        _params: VestingParams,
+    ) -> Result<()> {
+        let now = Clock::get()?;
+        let vesting_account = &mut ctx.accounts.vesting_account;
+        require!(vesting_account.is_active, VestingError::NotActive); // Is the vesting active?
 
-        let admin_ata = get_associated_token_address(          // ATA of the token minting wallet
+        check_destination(
+            &ctx.accounts.destination_token_account,
+            vesting_account,
             &ctx.accounts.token_info.mint_wallet_address,
-            &ctx.accounts.token_mint.key(),
-        );
+            ctx.accounts.destination_allowlist.as_deref(),
+        )?;
+        check_bulk_destination(
+            &ctx.accounts.approval_config,
+            vesting_account,
+            &ctx.accounts.destination_token_account.key(),
+        )?;
 
-        require_keys_eq!(
-            ctx.accounts.destination_token_account.mint,       // The destination account's mint must match
-            ctx.accounts.token_mint.key(),
-            VestingError::InvalidMint
-        );
+        let mut store = PlanStore::load(vesting_account, ctx.remaining_accounts, true)?;
+        let (amount, release_times) =
+            release_due(vesting_account, &mut store, now.unix_timestamp)?; // Only plans with release_time <= now
+        vesting_account.record_release(amount, now.unix_timestamp)?;
+        store.exit(ctx.program_id)?;                       // Persist released flags on every page
+
+        transfer_from_vault(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.origin_token_account.to_account_info(),
+            ctx.accounts.destination_token_account.to_account_info(),
+            ctx.accounts.vault_authority.to_account_info(),
+            &ctx.accounts.admin_config.seed_admin,
+            &ctx.accounts.token_vault.key(),
+            ctx.bumps.vault_authority,
+            amount,
+        )?;
 
-        let dest = &ctx.accounts.destination_token_account;    // Destination token account
-        let is_beneficiary_ata =
-            dest.key() == expected_ata && dest.owner == ctx.accounts.beneficiary.key(); // Is it the beneficiary's ATA?
-        let is_admin_ata =
-            dest.key() == admin_ata && dest.owner == ctx.accounts.token_info.mint_wallet_address; // Is it the admin's (mint wallet) ATA?
+        emit!(PlansReleased {
+            vesting_account: vesting_account.key(),
+            amount,
+            release_times,                                 // Which plans were settled
+        });
 
-        require!(
-            is_beneficiary_ata || is_admin_ata,                // Must be one of the two to be allowed
-            VestingError::Unauthorized
-        );
+        Ok(())
+    }
 
-        // The backend finds the plan chunk corresponding to the vesting_account, and among them, finds the plan where release_time == vesting_time
-        let vesting_plan = &mut ctx.accounts.plan_chunk;       // Collection of plans for this vesting (PDA)
-        let plan = &mut vesting_plan
-            .plans
-            .iter_mut()
-            .find(|p| p.release_time == vesting_time)          // Find a plan that matches the request time
-            .ok_or(VestingError::InvalidParameters)?;          // Error if not found
+    // Release matured plans for many vestings in one transaction
+    // remaining_accounts: [vesting_account, origin vault, destination ATA, plan storage...] per entry, where the
+    // plan storage is every plan chunk page (or the zero-copy chunk) and page_counts[i] says how many accounts it spans
+    pub fn batch_do_vesting<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchDoVesting<'info>>,
+        extra_accounts: Vec<u8>,                                       // Accounts after the fixed three of each entry: plan storage (none for Linear), then the allowlist for Allowlist vestings
+    ) -> Result<Vec<BatchReleaseResult>> {
+        let now = Clock::get()?;
+        let groups = batch_entries(ctx.remaining_accounts, &extra_accounts)?;
+
+        let mut results = Vec::with_capacity(groups.len());
+        for group in groups {
+            // Every check runs before anything is written; a failing entry is skipped and left untouched
+            let release = match prepare_batch_entry(group, ctx.accounts, now.unix_timestamp) {
+                Ok(release) => release,
+                Err(err) => {
+                    msg!("Skipped {}: {}", group[0].key(), err);
+                    results.push(BatchReleaseResult {
+                        vesting_account: group[0].key(),
+                        amount: 0,
+                        error_code: error_code_of(&err),
+                    });
+                    continue;
+                }
+            };
+            let amount = release_batch_entry(
+                release,
+                ctx.accounts,
+                ctx.bumps.vault_authority,
+                ctx.program_id,
+                now.unix_timestamp,
+            )?;
+            results.push(BatchReleaseResult {
+                vesting_account: group[0].key(),
+                amount,
+                error_code: 0,
+            });
+        }
+        Ok(results)
+    }
 
-        require!(
-            plan.release_time <= now.unix_timestamp,           // Has the time for that plan passed?
-            VestingError::VestingNotReached
-        );
-        require!(!plan.released, VestingError::AlreadyReleased); // Cannot proceed if the plan has already been released
-        require!(plan.amount == amount, VestingError::InvalidParameters); // The requested amount must match the plan's amount
+    // Beneficiary pulls every matured plan without waiting for the scheduler
+    // remaining_accounts: every plan chunk page of the vesting, or its zero-copy chunk once migrated
+    pub fn claim<'info>(ctx: Context<'_, '_, 'info, 'info, Claim<'info>>, _vesting_id: u64) -> Result<()> {
+        let now = Clock::get()?;
+        let vesting_account = &mut ctx.accounts.vesting_account;
+        require!(vesting_account.is_active, VestingError::NotActive); // Is the vesting active?
+        check_destination(
+            &ctx.accounts.beneficiary_token_account,
+            vesting_account,
+            &ctx.accounts.token_info.mint_wallet_address,
+            ctx.accounts.destination_allowlist.as_deref(),
+        )?;
 
-        let admin_key = ctx.accounts.admin.key();              // Cache the admin key
-        let token_vault_key = ctx.accounts.token_vault.key();  // Token vault key
+        let mut store = PlanStore::load(vesting_account, ctx.remaining_accounts, true)?;
+        let (amount, release_times) =
+            release_due(vesting_account, &mut store, now.unix_timestamp)?; // Sum and mark matured plans
+        vesting_account.record_release(amount, now.unix_timestamp)?;
+        store.exit(ctx.program_id)?;
+
+        transfer_from_vault(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.beneficiary_vault.to_account_info(),          // Beneficiary-specific vault
+            ctx.accounts.beneficiary_token_account.to_account_info(),  // Beneficiary's ATA
+            ctx.accounts.vault_authority.to_account_info(),
+            &ctx.accounts.admin_config.seed_admin,
+            &ctx.accounts.token_vault.key(),
+            ctx.bumps.vault_authority,
+            amount,
+        )?;
 
-        let (_vault_authority_pda, bump) = Pubkey::find_program_address( // Calculate the vault authority PDA
-            &[b"vault_auth", admin_key.as_ref(), token_vault_key.as_ref()],
-            ctx.program_id,
-        );
-        let seeds = &[
-            b"vault_auth",                                    // Authority PDA seeds
-            admin_key.as_ref(),
-            token_vault_key.as_ref(),
-            &[bump],
-        ];
+        emit!(PlansReleased {
+            vesting_account: vesting_account.key(),
+            amount,
+            release_times,
+        });
 
-        token::transfer(                                       // SPL Token transfer CPI
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),  // Token program
-                Transfer {
-                    from: ctx.accounts.origin_token_account.to_account_info(), // Source
-                    to: ctx.accounts.destination_token_account.to_account_info(), // Destination
-                    authority: ctx.accounts.vault_authority.to_account_info(), // Authority (PDA)
-                },
-                &[seeds],                                      // Sign with PDA signer
-            ),
-            amount,                                            // Transfer amount
+        Ok(())
+    }
+
+    // Anyone may release matured tokens, but only to the vesting's recorded destination
+    // remaining_accounts: every plan chunk page of the vesting, or its zero-copy chunk once migrated
+    pub fn crank_release<'info>(ctx: Context<'_, '_, 'info, 'info, CrankRelease<'info>>) -> Result<()> {
+        let now = Clock::get()?;
+        let vesting_account = &mut ctx.accounts.vesting_account;
+        require!(vesting_account.is_active, VestingError::NotActive); // Is the vesting active?
+        check_destination(
+            &ctx.accounts.destination_token_account,
+            vesting_account,
+            &ctx.accounts.token_info.mint_wallet_address,
+            ctx.accounts.destination_allowlist.as_deref(),
+        )?;
+        check_bulk_destination(
+            &ctx.accounts.approval_config,
+            vesting_account,
+            &ctx.accounts.destination_token_account.key(),
         )?;
 
-        vesting_account.released_amount = vesting_account       // Update cumulative released amount
-            .released_amount
-            .checked_add(amount)
-            .ok_or(VestingError::Overflow)?;
-        vesting_account.last_release_time = now.unix_timestamp; // Update last release time
-        plan.released = true;                                   // Mark this plan as completed
+        let mut store = PlanStore::load(vesting_account, ctx.remaining_accounts, true)?;
+        let (amount, release_times) = release_due(vesting_account, &mut store, now.unix_timestamp)?;
+        vesting_account.record_release(amount, now.unix_timestamp)?;
+        store.exit(ctx.program_id)?;
+
+        transfer_from_vault(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.beneficiary_vault.to_account_info(),
+            ctx.accounts.destination_token_account.to_account_info(),
+            ctx.accounts.vault_authority.to_account_info(),
+            &ctx.accounts.admin_config.seed_admin,
+            &ctx.accounts.token_vault.key(),
+            ctx.bumps.vault_authority,
+            amount,
+        )?;
+
+        // Reimburse the caller from the mint's tip pool; an empty pool just skips the tip
+        if let Some(tip_pool) = &ctx.accounts.tip_pool {
+            let pool_info = tip_pool.to_account_info();
+            let rent_floor = Rent::get()?.minimum_balance(pool_info.data_len());
+            pay_crank_tip(&pool_info, &ctx.accounts.cranker, tip_pool.tip_lamports, rent_floor)?;
+        }
+
+        emit!(PlansReleased {
+            vesting_account: vesting_account.key(),
+            amount,
+            release_times,
+        });
+
+        Ok(())
+    }
+
+    // Replace the members of one role (admin only; the admin implicitly holds every role)
+    pub fn set_role_members(ctx: Context<SetRoleMembers>, role: Role, members: Vec<Pubkey>) -> Result<()> {
+        require!(members.len() <= MAX_ROLE_MEMBERS, VestingError::InvalidParameters);
+        consume_approval(
+            &ctx.accounts.approval_config,
+            ctx.accounts.proposal.as_deref_mut(),
+            &ProposalOperation::SetRoleMembers { role, members: members.clone() },
+        )?;
+        *ctx.accounts.roles.members_mut(role) = members;
+        Ok(())
+    }
 
+    // Configure the per-mint tip paid to crank_release callers (fund it by sending SOL to the PDA)
+    pub fn set_tip_pool(ctx: Context<SetTipPool>, tip_lamports: u64) -> Result<()> {
+        let tip_pool = &mut ctx.accounts.tip_pool;
+        tip_pool.token_mint = ctx.accounts.token_mint.key();
+        tip_pool.tip_lamports = tip_lamports;              // 0 disables tips
         Ok(())
     }
 
@@ -208,11 +467,6 @@ pub mod vesting {                                      // Start of the Anchor pr
         let vesting_account = &mut ctx.accounts.vesting_account; // Mutable reference
         let _clock = Clock::get()?;                              // (Unused) time
 
-        // Check if admin
-        require!(
-            ctx.accounts.admin.key() == ctx.accounts.admin_config.admin,
-            VestingError::Unauthorized
-        );
 
         require!(
             ctx.accounts.token_info.token_mint == ctx.accounts.token_mint.key(), // Verify if it is a registered token
@@ -221,6 +475,15 @@ pub mod vesting {                                      // Start of the Anchor pr
 
         // Parameter validation
         require!(params.total_amount > 0, VestingError::InvalidParameters);
+        require!(
+            params.kind != VestingKind::Linear || params.start_time < params.end_time, // Linear vestings need a non-empty window
+            VestingError::InvalidParameters
+        );
+        require!(
+            params.cliff_time == 0                                  // 0 = no cliff
+                || (params.start_time <= params.cliff_time && params.cliff_time <= params.end_time),
+            VestingError::InvalidParameters
+        );
 
         require!(
             ctx.accounts.beneficiary_vault.key() != ctx.accounts.parent_vault.key(), // Same vault is prohibited
@@ -236,7 +499,7 @@ pub mod vesting {                                      // Start of the Anchor pr
         vesting_account.total_amount = params.total_amount;           // Total vesting amount
         vesting_account.released_amount = params.released_amount;     // Already released amount (initial value allowed)
         vesting_account.start_time = params.start_time;               // Start time
-        // vesting_account.cliff_time = params.cliff_time;            // (Comment) Cliff not used
+        vesting_account.cliff_time = params.cliff_time;               // Cliff (0 = none)
         vesting_account.end_time = params.end_time;                   // End time
         vesting_account.token_mint = ctx.accounts.token_mint.key();   // Vesting token mint
         vesting_account.token_vault = ctx.accounts.token_vault.key(); // Token vault
@@ -246,8 +509,9 @@ pub mod vesting {                                      // Start of the Anchor pr
         vesting_account.category = params.category.clone();            // Category (Team/Marketing, etc.)
         vesting_account.is_active = true;                              // Activate
         vesting_account.parent_vault = ctx.accounts.parent_vault.key(); // Record parent vault
+        vesting_account.kind = params.kind;                            // Plan-based or linear
 
-        let admin_key = ctx.accounts.admin.key();
+        let admin_key = ctx.accounts.admin_config.seed_admin;
         let token_vault_key = ctx.accounts.token_vault.key();
 
         let (_vault_auth, vault_auth_bump) = Pubkey::find_program_address( // Authority PDA (bump)
@@ -285,11 +549,6 @@ pub mod vesting {                                      // Start of the Anchor pr
         let vesting_account = &mut ctx.accounts.vesting_account;
         let _clock = Clock::get()?;
 
-        // Check if admin
-        require!(
-            ctx.accounts.admin.key() == ctx.accounts.admin_config.admin,
-            VestingError::Unauthorized
-        );
 
         require!(
             ctx.accounts.token_info.token_mint == ctx.accounts.token_mint.key(),
@@ -301,8 +560,17 @@ pub mod vesting {                                      // Start of the Anchor pr
             VestingError::InvalidParameters
         );
 
-        let plans = &mut ctx.accounts.parent_plan_chunk.plans;         // Parent plans
-        require!(!plans.is_empty(), VestingError::ParentPlanNotFound);  // Not possible if parent plan does not exist
+        require!(params.kind == VestingKind::Plan, VestingError::InvalidParameters); // Child allocations are carved from parent plans
+        require!(
+            params.cliff_time == 0
+                || (params.start_time <= params.cliff_time && params.cliff_time <= params.end_time),
+            VestingError::InvalidParameters
+        );
+        let parent_plans = read_parent_plans(
+            &ctx.accounts.parent_plan_chunk,
+            &ctx.accounts.parent_vesting_account.key(),
+        )?;                                                            // Parent plans
+        require!(!parent_plans.is_empty(), VestingError::ParentPlanNotFound); // Not possible if parent plan does not exist
 
         let amount_to_transfer = params
             .total_amount
@@ -313,6 +581,7 @@ pub mod vesting {                                      // Start of the Anchor pr
         vesting_account.total_amount = params.total_amount;
         vesting_account.released_amount = params.released_amount;
         vesting_account.start_time = params.start_time;
+        vesting_account.cliff_time = params.cliff_time;
         vesting_account.end_time = params.end_time;
         vesting_account.token_mint = ctx.accounts.token_mint.key();
         vesting_account.token_vault = ctx.accounts.token_vault.key();
@@ -322,8 +591,13 @@ pub mod vesting {                                      // Start of the Anchor pr
         vesting_account.category = params.category.clone();
         vesting_account.is_active = true;
         vesting_account.parent_vault = ctx.accounts.parent_vault.key();
+        vesting_account.kind = params.kind;
 
-        let admin_key = ctx.accounts.admin.key();
+        let allocation = &mut ctx.accounts.allocation;                 // Filled by append_yearly_plan
+        allocation.parent_vesting = ctx.accounts.parent_vesting_account.key();
+        allocation.child_vesting = vesting_account.key();
+
+        let admin_key = ctx.accounts.admin_config.seed_admin;
         let token_vault_key = ctx.accounts.token_vault.key();
 
         let (_vault_auth, vault_auth_bump) = Pubkey::find_program_address(
@@ -354,511 +628,3827 @@ pub mod vesting {                                      // Start of the Anchor pr
         Ok(())
     }
 
-    pub fn append_yearly_plan(
-        ctx: Context<AppendYearlyPlan>,
+    pub fn append_yearly_plan<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AppendYearlyPlan<'info>>,
+        chunk_index: u16,                                              // Page to append to (next index opens a new page)
         plans: Vec<YearlyPlan>,
+        validation: ScheduleValidation,                                // Other pages of the vesting go in remaining_accounts, in page order
     ) -> Result<()> {                                                  // Add yearly plan (and parent deduction logic)
-        require!(
-            ctx.accounts.admin_config.admin == ctx.accounts.admin.key(), // Check admin
-            VestingError::Unauthorized
-        );
-
-        let chunk = &mut ctx.accounts.plan_chunk;                       // Target plan chunk
-        let deduct = ctx.accounts.vesting_account.token_vault.key()
-            != ctx.accounts.vesting_account.parent_vault.key();         // Perform parent deduction if different from parent vault
-
-        if deduct {
-            // Check if owned by this program
-            let ai = ctx
-                .accounts
-                .parent_plan_chunk
-                .as_ref()
-                .unwrap()
-                .to_account_info();                                     // Option unwrap (assuming it exists beforehand)
-            require!(ai.owner == ctx.program_id, VestingError::Unauthorized); // Must be owned by the same program
-
-            let parent_chunk = ctx
+        check_plan_timelock(&ctx.accounts.plan_timelock, &ctx.accounts.vesting_account)?;
+        let vesting_account = &ctx.accounts.vesting_account;
+        if vesting_account.token_vault != vesting_account.parent_vault {   // The parent's plans are deducted as well
+            let parent_vesting_account = ctx
                 .accounts
-                .parent_plan_chunk
-                .as_deref_mut()
-                .ok_or(VestingError::ParentPlanNotFound)?;             // Check for parent plan existence
-
-            let user_tge_time = plans.first().map(|p| p.release_time);  // User plan first release (assuming TGE)
-            let parent_tge_time = parent_chunk.plans.first().map(|p| p.release_time); // Parent first release
-            let tge_equal = user_tge_time == parent_tge_time;           // Check if TGE is the same
-
-            if tge_equal {
-                // If TGE is the same, 1:1 matching (deduct only for released == false)
-                for (user_plan, parent_plan) in plans.iter().zip(parent_chunk.plans.iter_mut()) {
-                    if !user_plan.released && !parent_plan.released {
-                        require!(
-                            parent_plan.amount >= user_plan.amount,
-                            VestingError::InsufficientAmount
-                        );
-
-                        parent_plan.amount = parent_plan
-                            .amount
-                            .checked_sub(user_plan.amount)
-                            .ok_or(VestingError::Overflow)?;           // Deduct amount from parent plan
-                    }
-                }
-            } else {
-                // If TGE is different: match and deduct from user false[0] and parent false[1]
-                // Extract only user plans where released == false
-                let user_unreleased: Vec<&YearlyPlan> =
-                    plans.iter().filter(|p| !p.released).collect();
-                let parent_unreleased: Vec<&mut YearlyPlan> = parent_chunk
-                    .plans
-                    .iter_mut()
-                    .filter(|p| !p.released)
-                    .collect();
-
-                // 1:1 correspondence from user 0, foundation 1
-                let mut parent_iter = parent_unreleased.into_iter().skip(1);
-
-                for user_plan in user_unreleased {
-                    if let Some(parent_plan) = parent_iter.next() {
-                        // Return error if foundation amount is insufficient
-                        require!(
-                            parent_plan.amount >= user_plan.amount,
-                            VestingError::InsufficientAmount
-                        );
-
-                        // Process deduction identically even if it's 0
-                        parent_plan.amount = parent_plan
-                            .amount
-                            .checked_sub(user_plan.amount)
-                            .ok_or(VestingError::Overflow)?;
-                    } else {
-                        // If foundation plan is insufficient, stop further deductions and exit
-                        break;
-                    }
-                }
-            }
+                .parent_vesting_account
+                .as_deref()
+                .ok_or(VestingError::ParentPlanNotFound)?;
+            check_plan_timelock(&ctx.accounts.plan_timelock, parent_vesting_account)?;
         }
+        append_plans(
+            &mut ctx.accounts.vesting_account,
+            &mut ctx.accounts.plan_chunk,
+            chunk_index,
+            plans,
+            ctx.accounts.parent_plan_chunk.as_deref(),
+            ctx.accounts.allocation.as_mut(),
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
 
-        chunk.vesting_account = ctx.accounts.vesting_account.key(); // Indicate the owning vesting account
-        chunk.plans.extend(plans);                                   // Add plans
-        Ok(())
+        let chunk = &ctx.accounts.plan_chunk;
+        validate_schedule(
+            &ctx.accounts.vesting_account,
+            chunk_index,
+            &chunk.plans,
+            ctx.remaining_accounts,
+            validation,
+        )
     }
 
-    pub fn update_plan_chunk(ctx: Context<UpdatePlanChunk>, plans: Vec<YearlyPlan>) -> Result<()> { // Replace all plans
+    pub fn update_plan_chunk<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdatePlanChunk<'info>>,
+        chunk_index: u16,                                              // Page to replace (PDA seed)
+        plans: Vec<YearlyPlan>,
+        validation: ScheduleValidation,                                // Other pages of the vesting go in remaining_accounts, in page order
+        allow_amount_change: bool,                                     // Permit a different unreleased total on this page
+    ) -> Result<()> {                                                  // Replace all unreleased plans
+        check_plan_timelock(&ctx.accounts.plan_timelock, &ctx.accounts.vesting_account)?;
+        check_plans_unallocated(&ctx.accounts.vesting_account)?;
+        consume_approval(
+            &ctx.accounts.approval_config,
+            ctx.accounts.proposal.as_deref_mut(),
+            &ProposalOperation::ReplacePlans {
+                vesting_account: ctx.accounts.vesting_account.key(),
+                chunk_index,
+                plans: plans.clone(),
+                validation,
+                allow_amount_change,
+            },
+        )?;
         let plan_chunk = &mut ctx.accounts.plan_chunk;
+        replace_plans(plan_chunk, plans, allow_amount_change)?;
+
+        validate_schedule(
+            &ctx.accounts.vesting_account,
+            chunk_index,
+            &plan_chunk.plans,
+            ctx.remaining_accounts,
+            validation,
+        )
+    }
 
-        plan_chunk.plans.clear();                                     // Delete existing
-        plan_chunk.plans.extend(plans);                               // Refill with new
+    // Apply one indexed edit to a plan chunk page instead of replacing it
+    pub fn patch_plan_chunk<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PatchPlanChunk<'info>>,
+        chunk_index: u16,                                              // Page to patch (PDA seed)
+        patch: PlanPatch,
+        validation: ScheduleValidation,                                // Other pages of the vesting go in remaining_accounts, in page order
+    ) -> Result<()> {
+        check_plan_timelock(&ctx.accounts.plan_timelock, &ctx.accounts.vesting_account)?;
+        check_plans_unallocated(&ctx.accounts.vesting_account)?;
+        validation.check_patch()?;
+        consume_approval(
+            &ctx.accounts.approval_config,
+            ctx.accounts.proposal.as_deref_mut(),
+            &ProposalOperation::PatchPlans {
+                vesting_account: ctx.accounts.vesting_account.key(),
+                chunk_index,
+                patch: patch.clone(),
+                validation,
+            },
+        )?;
+        let plan_chunk = &mut ctx.accounts.plan_chunk;
+        patch.apply(&mut plan_chunk.plans)?;
+        require!(plan_chunk.plans.len() <= MAX_PLANS, VestingError::InsufficientSpace); // Page capacity
+
+        validate_schedule(
+            &ctx.accounts.vesting_account,
+            chunk_index,
+            &plan_chunk.plans,
+            ctx.remaining_accounts,
+            validation,
+        )
+    }
 
+    // Set the notice period for plan chunk changes (admin); shortening it only applies after the current delay
+    pub fn set_plan_timelock(ctx: Context<SetPlanTimelock>, delay: i64) -> Result<()> {
+        require!(delay >= 0, VestingError::InvalidParameters);
+        consume_approval(
+            &ctx.accounts.approval_config,
+            ctx.accounts.proposal.as_deref_mut(),
+            &ProposalOperation::SetPlanTimelock { delay },
+        )?;
+        let now = Clock::get()?.unix_timestamp;
+        let plan_timelock = &mut ctx.accounts.plan_timelock;
+        let current = plan_timelock.delay_at(now);
+
+        let effective_at = if delay >= current {
+            plan_timelock.delay = delay;                                // Longer notice applies right away
+            plan_timelock.pending_delay = 0;
+            plan_timelock.pending_delay_at = 0;
+            now
+        } else {
+            let effective_at = now.checked_add(current).ok_or(VestingError::Overflow)?;
+            plan_timelock.delay = current;
+            plan_timelock.pending_delay = delay;
+            plan_timelock.pending_delay_at = effective_at;
+            effective_at
+        };
+
+        emit!(PlanTimelockSet { delay, effective_at });
         Ok(())
     }
 
-    // Emergency stop function (change is_active state)
-    pub fn emergency_stop(ctx: Context<EmergencyStop>) -> Result<()> { // Toggle between active/inactive
-        let vesting_account = &mut ctx.accounts.vesting_account;
-        vesting_account.is_active = !vesting_account.is_active;        // Toggle
+    // Stage a change to one plan chunk page; it can be executed once the timelock delay has passed
+    pub fn queue_plan_change(ctx: Context<QueuePlanChange>, chunk_index: u16, change: PlanChange) -> Result<()> {
+        require!(
+            ctx.accounts.vesting_account.kind == VestingKind::Plan,     // Linear vestings have no plans
+            VestingError::InvalidParameters
+        );
+        match &change {
+            PlanChange::Replace { .. } => check_plans_unallocated(&ctx.accounts.vesting_account)?,
+            PlanChange::Patch { validation, .. } => {
+                check_plans_unallocated(&ctx.accounts.vesting_account)?;
+                validation.check_patch()?;
+            }
+            PlanChange::PatchZeroCopy { validation, .. } => {
+                check_plans_unallocated(&ctx.accounts.vesting_account)?;
+                validation.check_patch()?;
+                require!(chunk_index == 0, VestingError::PlanChunkMismatch); // A migrated vesting has one zero-copy chunk
+            }
+            _ => {}
+        }
+        let now = Clock::get()?.unix_timestamp;
+        let eta = now
+            .checked_add(ctx.accounts.plan_timelock.delay_at(now))
+            .ok_or(VestingError::Overflow)?;
 
+        let pending_change = &mut ctx.accounts.pending_change;
+        pending_change.vesting_account = ctx.accounts.vesting_account.key();
+        pending_change.chunk_index = chunk_index;
+        pending_change.proposer = ctx.accounts.admin.key();
+        pending_change.change = change.clone();
+        pending_change.eta = eta;
+
+        emit!(PlanChangeQueued {
+            pending_change: pending_change.key(),
+            vesting_account: pending_change.vesting_account,
+            chunk_index,
+            proposer: pending_change.proposer,
+            change,
+            eta,
+        });
         Ok(())
     }
 
-    pub fn close_vesting_account(_ctx: Context<CloseVestingAccount>) -> Result<()> { // (Handle only)
-        Ok(())                                                         // Actual resource deallocation is performed by the account annotation
+    // Drop a staged change before (or after) it becomes executable; rent goes back to the proposer
+    pub fn cancel_plan_change(ctx: Context<CancelPlanChange>, chunk_index: u16) -> Result<()> {
+        emit!(PlanChangeCancelled {
+            pending_change: ctx.accounts.pending_change.key(),
+            vesting_account: ctx.accounts.vesting_account.key(),
+            chunk_index,
+            cancelled_by: ctx.accounts.admin.key(),
+        });
+        Ok(())                                                         // Closed by the account annotation
     }
 
-    pub fn remove_admin(ctx: Context<RemoveAdmin>) -> Result<()> {    // Remove admin (deployer only)
+    // Apply a staged change whose delay has passed
+    pub fn execute_plan_change<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecutePlanChange<'info>>,
+        chunk_index: u16,                                              // Page the change targets (PDA seed)
+    ) -> Result<()> {                                                  // Other pages of the vesting go in remaining_accounts, in page order
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= ctx.accounts.pending_change.eta, VestingError::TimelockNotExpired);
+        let change = ctx.accounts.pending_change.change.clone();
+        let vesting_key = ctx.accounts.vesting_account.key();
+        let payer = ctx.accounts.admin.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+
+        let validation = match change {
+            PlanChange::Append { plans, validation } => {
+                append_plans(
+                    &mut ctx.accounts.vesting_account,
+                    &mut ctx.accounts.plan_chunk,
+                    chunk_index,
+                    plans,
+                    ctx.accounts.parent_plan_chunk.as_deref(),
+                    ctx.accounts.allocation.as_deref_mut(),
+                    &payer,
+                    &system_program,
+                )?;
+                validation
+            }
+            PlanChange::Replace { plans, validation, allow_amount_change } => {
+                require_keys_eq!(ctx.accounts.plan_chunk.vesting_account, vesting_key, VestingError::PlanChunkMismatch); // Page must exist
+                check_plans_unallocated(&ctx.accounts.vesting_account)?;
+                consume_approval(                                      // Same M-of-N gate as update_plan_chunk
+                    &ctx.accounts.approval_config,
+                    ctx.accounts.proposal.as_deref_mut(),
+                    &ProposalOperation::ReplacePlans {
+                        vesting_account: vesting_key,
+                        chunk_index,
+                        plans: plans.clone(),
+                        validation,
+                        allow_amount_change,
+                    },
+                )?;
+                replace_plans(&mut ctx.accounts.plan_chunk, plans, allow_amount_change)?;
+                validation
+            }
+            PlanChange::Patch { patch, validation } => {
+                require_keys_eq!(ctx.accounts.plan_chunk.vesting_account, vesting_key, VestingError::PlanChunkMismatch); // Page must exist
+                check_plans_unallocated(&ctx.accounts.vesting_account)?;
+                validation.check_patch()?;
+                consume_approval(                                      // Same M-of-N gate as patch_plan_chunk
+                    &ctx.accounts.approval_config,
+                    ctx.accounts.proposal.as_deref_mut(),
+                    &ProposalOperation::PatchPlans {
+                        vesting_account: vesting_key,
+                        chunk_index,
+                        patch: patch.clone(),
+                        validation,
+                    },
+                )?;
+                let plan_chunk = &mut ctx.accounts.plan_chunk;
+                patch.apply(&mut plan_chunk.plans)?;
+                require!(plan_chunk.plans.len() <= MAX_PLANS, VestingError::InsufficientSpace); // Page capacity
+                validation
+            }
+            PlanChange::PatchZeroCopy { .. } => return err!(VestingError::InvalidParameters), // execute_zero_copy_plan_change
+        };
+
+        let plan_chunk = &ctx.accounts.plan_chunk;
+        resize_account(
+            &plan_chunk.to_account_info(),
+            &payer,
+            &system_program,
+            plan_chunk_space(plan_chunk.plans.len()),                  // Rent follows the plans stored
+        )?;
+
+        emit!(PlanChangeExecuted {
+            pending_change: ctx.accounts.pending_change.key(),
+            vesting_account: vesting_key,
+            chunk_index,
+        });
+
+        validate_schedule(
+            &ctx.accounts.vesting_account,
+            chunk_index,
+            &plan_chunk.plans,
+            ctx.remaining_accounts,
+            validation,
+        )
+    }
+
+    // execute_plan_change for vestings on zero-copy storage
+    pub fn execute_zero_copy_plan_change(ctx: Context<ExecuteZeroCopyPlanChange>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= ctx.accounts.pending_change.eta, VestingError::TimelockNotExpired);
+        let PlanChange::PatchZeroCopy { patch, validation } = ctx.accounts.pending_change.change.clone() else {
+            return err!(VestingError::InvalidParameters);              // Page changes go through execute_plan_change
+        };
+        let vesting_key = ctx.accounts.vesting_account.key();
+        check_plans_unallocated(&ctx.accounts.vesting_account)?;
+        consume_approval(                                              // Same M-of-N gate as patch_zero_copy_plans
+            &ctx.accounts.approval_config,
+            ctx.accounts.proposal.as_deref_mut(),
+            &ProposalOperation::PatchZeroCopyPlans {
+                vesting_account: vesting_key,
+                patch: patch.clone(),
+                validation,
+            },
+        )?;
+        patch_zero_copy(&ctx.accounts.zero_copy_plans, &ctx.accounts.vesting_account, &patch, validation)?;
+
+        emit!(PlanChangeExecuted {
+            pending_change: ctx.accounts.pending_change.key(),
+            vesting_account: vesting_key,
+            chunk_index: 0,
+        });
+        Ok(())
+    }
+
+    // Build the first plan chunk page on-chain from a compact template
+    pub fn generate_plan_schedule(ctx: Context<GeneratePlanSchedule>, template: ScheduleTemplate) -> Result<()> {
+        check_plan_timelock(&ctx.accounts.plan_timelock, &ctx.accounts.vesting_account)?;
+        let vesting_account = &mut ctx.accounts.vesting_account;
+        require!(vesting_account.kind == VestingKind::Plan, VestingError::InvalidParameters); // Linear vestings have no plans
+        require!(vesting_account.plan_chunk_count == 0, VestingError::InvalidParameters);    // Only for an empty schedule
         require!(
-            ctx.accounts.deployer_admin.deployer == ctx.accounts.deployer.key(), // Check deployer
-            VestingError::NotDeployAdmin
+            vesting_account.token_vault == vesting_account.parent_vault, // Child schedules need the parent deduction of append_yearly_plan
+            VestingError::InvalidParameters
         );
 
-        Ok(())                                                         // Actual close is handled in Accounts
+        let unreleased = vesting_account
+            .total_amount
+            .checked_sub(vesting_account.released_amount)
+            .ok_or(VestingError::Overflow)?;
+        let plans = template.plans(unreleased)?;
+        vesting_account.plan_chunk_count = 1;
+
+        let plan_chunk = &mut ctx.accounts.plan_chunk;
+        plan_chunk.vesting_account = vesting_account.key();
+        plan_chunk.plans = plans;
+
+        validate_schedule(vesting_account, 0, &plan_chunk.plans, &[], ScheduleValidation::Strict)
     }
 
-    pub fn init_token_info(ctx: Context<InitTokenInfo>, args: TokenInfoArgs) -> Result<()> { // Register token metadata
+    // Choose where released tokens may be sent for this vesting
+    pub fn set_destination_policy(
+        ctx: Context<SetDestinationPolicy>,
+        policy: DestinationPolicy,
+        destinations: Vec<Pubkey>,                     // Only used by DestinationPolicy::Allowlist
+    ) -> Result<()> {
+        require!(destinations.len() <= MAX_DESTINATIONS, VestingError::InvalidParameters);
         require!(
-            ctx.accounts.admin_config.admin == ctx.accounts.scheduler_admin.key(), // Is scheduler an admin?
-            VestingError::Unauthorized
+            policy != DestinationPolicy::Allowlist || !destinations.is_empty(), // An empty allowlist is BeneficiaryOnly
+            VestingError::InvalidParameters
         );
 
-        let token_info = &mut ctx.accounts.token_info;                 // TokenInfo PDA
-
-        token_info.token_name = args.token_name;                       // Set name/symbol/total supply/mint/minting wallet
-        token_info.token_symbol = args.token_symbol;
-        token_info.total_supply = args.total_supply;
-        token_info.token_mint = args.token_mint;
-        token_info.mint_wallet_address = args.mint_wallet_address;
+        let allowlist = &mut ctx.accounts.destination_allowlist;
+        allowlist.vesting_account = ctx.accounts.vesting_account.key();
+        allowlist.destinations = destinations;
 
+        ctx.accounts.vesting_account.destination_policy = policy;
         Ok(())
     }
 
-}
+    // Upgrade a vesting account written with the legacy layout (no kind/cliff_time)
+    pub fn migrate_vesting_account(ctx: Context<MigrateVestingAccount>) -> Result<()> {
+        let info = ctx.accounts.vesting_account.to_account_info();
+        let migrated = migrated_vesting_account(&info.try_borrow_data()?)?;
 
-// Store vesting information
-#[account]
-pub struct VestingAccount {                         // PDA to store vesting metadata
-    pub beneficiary: Pubkey,                        // Beneficiary
-    pub total_amount: u64,                          // Total amount
-    pub released_amount: u64,                       // Cumulative released amount
-    pub start_time: i64,                            // Start time (Unix)
-    pub end_time: i64,                              // End time (Unix)
-    pub last_release_time: i64,                     // Last release time
-    pub token_mint: Pubkey,                         // Token mint
-    pub token_vault: Pubkey,                        // Vault (for this vesting)
-    pub beneficiary_vault: Pubkey,                  // Beneficiary vault (PDA)
-    pub category: String,                           // Category (Team/Marketing, etc.)
-    pub is_active: bool,                            // Is active?
-    pub destination_token_account: Pubkey,          // Final receiving token account (e.g., ATA)
-    pub parent_vault: Pubkey,                       // Parent vault (primary wallet)
-}
+        grow_account(
+            &info,
+            &ctx.accounts.admin.to_account_info(),          // Admin tops up rent for the extra space
+            &ctx.accounts.system_program.to_account_info(),
+            VESTING_ACCOUNT_SPACE,
+        )?;
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct YearlyPlan {                             // Yearly plan unit
-    pub release_time: i64,                          // Release time
-    pub amount: u64,                                // Release amount
-    pub released: bool,                             // Has it been released already?
-}
+        let mut data = info.try_borrow_mut_data()?;
+        migrated.try_serialize(&mut &mut data[..])?;       // Rewrites discriminator + fields
 
-#[account]
-pub struct VestingPlanChunk {                       // A bundle of plans (PDA)
-    pub vesting_account: Pubkey,                    // Which vesting does it belong to
-    pub plans: Vec<YearlyPlan>,                     // Array of YearlyPlan
-}
+        Ok(())
+    }
 
-// Account to store admin information
-#[account]
-pub struct AdminConfig {                            // Admin configuration (PDA)
-    pub admin: Pubkey,                              // Admin key
-}
+    // Move a legacy plan chunk ([b"plans", vesting_account], no partial releases) to page 0
+    pub fn migrate_plan_chunk(ctx: Context<MigratePlanChunk>) -> Result<()> {
+        let legacy_info = ctx.accounts.legacy_plan_chunk.to_account_info();
+        let migrated = migrated_plan_chunk(&legacy_info.try_borrow_data()?)?;
+        require_keys_eq!(
+            migrated.vesting_account,
+            ctx.accounts.vesting_account.key(),
+            VestingError::InvalidParameters
+        );
 
-#[account]
-pub struct DeployAdmin {                            // Deployer configuration (PDA)
-    pub deployer: Pubkey,                           // Deployer key
-}
+        grow_account(
+            &ctx.accounts.plan_chunk.to_account_info(),
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            plan_chunk_space(migrated.plans.len()),
+        )?;
 
-#[derive(Accounts)]
-pub struct InitializeDeployer<'info> {              // initialize_deployer context
-    #[account(mut)]
-    pub deployer: Signer<'info>, // Deployer = signer
+        let plan_chunk = &mut ctx.accounts.plan_chunk;
+        plan_chunk.vesting_account = migrated.vesting_account;
+        plan_chunk.plans = migrated.plans;
+        ctx.accounts.vesting_account.plan_chunk_count = 1;
 
-    #[account(
-        init,
-        payer = deployer,
-        space = 8 + 32, // discriminator + pubkey
-        seeds = [b"deploy_admin"],
-        bump
-    )]
-    pub deploy_admin: Account<'info, DeployAdmin>,  // PDA: ("deploy_admin")
+        close_program_account(&legacy_info, &ctx.accounts.admin.to_account_info())?; // Legacy rent back to admin
+        Ok(())
+    }
+
+    // Create the allocation ledger of a child vesting made before ledgers existed, from its recorded deductions
+    // remaining_accounts: each parent plan chunk page named in `entries`, or the parent's zero-copy chunk once
+    // migrated (entries then name that chunk)
+    pub fn migrate_plan_allocation<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MigratePlanAllocation<'info>>,
+        entries: Vec<AllocationEntry>,
+    ) -> Result<()> {
+        let parent_vesting = ctx.accounts.parent_vesting_account.key();
+        let mut parent_pages = Vec::with_capacity(ctx.remaining_accounts.len());
+        for info in ctx.remaining_accounts {
+            parent_pages.push((info.key(), read_parent_plans(info, &parent_vesting)?));
+        }
+        check_allocation_entries(&entries, &parent_pages)?;
+
+        let allocation = &mut ctx.accounts.allocation;
+        allocation.parent_vesting = parent_vesting;
+        allocation.child_vesting = ctx.accounts.vesting_account.key();
+        allocation.entries = entries;
+        Ok(())
+    }
+
+    // Move every plan chunk page into one zero-copy chunk; afterwards do_vesting releases through
+    // zero_copy_plans, patch_zero_copy_plans edits it, and the other release, info, close and revoke
+    // instructions take it in place of the pages.
+    // remaining_accounts: every plan chunk page of the vesting (closed as well)
+    pub fn migrate_to_zero_copy_plans<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MigrateToZeroCopyPlans<'info>>,
+    ) -> Result<()> {
+        let vesting_account = &mut ctx.accounts.vesting_account;
+        require!(vesting_account.kind == VestingKind::Plan, VestingError::InvalidParameters); // Linear vestings have no plans
+        let pages = load_plan_pages(vesting_account, ctx.remaining_accounts, true)?;
+        let plans: Vec<YearlyPlan> = pages.iter().flat_map(|p| p.plans.iter().cloned()).collect();
+
+        let mut zero_copy_plans = ctx.accounts.zero_copy_plans.load_init()?;
+        zero_copy_plans.vesting_account = vesting_account.key();
+        zero_copy_plans.set_plans(&plans)?;
+
+        let admin = ctx.accounts.admin.to_account_info();
+        for page in &pages {
+            close_program_account(&page.to_account_info(), &admin)?;  // Return page rent to admin
+        }
+        vesting_account.plan_chunk_count = 0;                          // No Borsh pages left
+        Ok(())
+    }
+
+    // patch_plan_chunk for vestings on zero-copy storage
+    pub fn patch_zero_copy_plans(
+        ctx: Context<PatchZeroCopyPlans>,
+        patch: PlanPatch,
+        validation: ScheduleValidation,
+    ) -> Result<()> {
+        consume_approval(
+            &ctx.accounts.approval_config,
+            ctx.accounts.proposal.as_deref_mut(),
+            &ProposalOperation::PatchZeroCopyPlans {
+                vesting_account: ctx.accounts.vesting_account.key(),
+                patch: patch.clone(),
+                validation,
+            },
+        )?;
+        check_plan_timelock(&ctx.accounts.plan_timelock, &ctx.accounts.vesting_account)?;
+        check_plans_unallocated(&ctx.accounts.vesting_account)?;
+        patch_zero_copy(&ctx.accounts.zero_copy_plans, &ctx.accounts.vesting_account, &patch, validation)
+    }
+
+    // Vesting summary for dashboards/scripts (simulate and read the return data)
+    // remaining_accounts: every plan chunk page of the vesting, or its zero-copy chunk once migrated
+    pub fn get_vesting_info<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetVestingInfo<'info>>,
+    ) -> Result<VestingInfo> {
+        let now = Clock::get()?;
+        let store = PlanStore::load(&ctx.accounts.vesting_account, ctx.remaining_accounts, false)?;
+        ctx.accounts.vesting_account.info(&store.plans()?, now.unix_timestamp)
+    }
+
+    // Remaining balance of the plan scheduled at `vesting_time` (simulate and read the return data)
+    // remaining_accounts: every plan chunk page of the vesting, or its zero-copy chunk once migrated
+    pub fn get_plan_balance<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetPlanBalance<'info>>,
+        vesting_time: i64,
+    ) -> Result<PlanBalance> {
+        let store = PlanStore::load(&ctx.accounts.vesting_account, ctx.remaining_accounts, false)?;
+        let plans = store.plans()?;
+        let plan = plans
+            .iter()
+            .find(|p| p.release_time == vesting_time)
+            .ok_or(VestingError::InvalidParameters)?;
+
+        Ok(PlanBalance {
+            amount: plan.amount,
+            released_amount: plan.released_amount,
+            remaining_amount: plan.remaining_amount(),
+        })
+    }
+
+    // Emergency stop function (change is_active state)
+    pub fn emergency_stop(ctx: Context<EmergencyStop>) -> Result<()> { // Toggle between active/inactive
+        let vesting_account = &mut ctx.accounts.vesting_account;
+        vesting_account.is_active = !vesting_account.is_active;        // Toggle
+
+        Ok(())
+    }
+
+    // remaining_accounts: every plan chunk page of the vesting, or its zero-copy chunk once migrated (closed as well)
+    pub fn close_vesting_account<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseVestingAccount<'info>>,
+    ) -> Result<()> {
+        let store = PlanStore::load(&ctx.accounts.vesting_account, ctx.remaining_accounts, true)?;
+        store.close(&ctx.accounts.admin.to_account_info())?;          // Return plan storage rent to admin
+        Ok(())                                                         // vesting_account itself is closed by the account annotation
+    }
+
+    // Cancel a child vesting: credit its unpaid allocations back to the parent plans, return the
+    // unreleased tokens to parent_vault and close the child.
+    // remaining_accounts: every child plan chunk page (or its zero-copy chunk), then each parent page named in
+    // the ledger (or the parent's zero-copy chunk)
+    pub fn revoke_child_vesting<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RevokeChildVesting<'info>>,
+    ) -> Result<()> {
+        consume_approval(
+            &ctx.accounts.approval_config,
+            ctx.accounts.proposal.as_deref_mut(),
+            &ProposalOperation::RevokeChildVesting {
+                vesting_account: ctx.accounts.vesting_account.key(),
+            },
+        )?;
+        let vesting_account = &ctx.accounts.vesting_account;
+        let allocation = &ctx.accounts.allocation;
+        let (child_store, child_count) = PlanStore::load_prefix(vesting_account, ctx.remaining_accounts, true)?;
+        let child_plans = child_store.plans()?;
+        let parent_infos = &ctx.remaining_accounts[child_count..];
+
+        let parent_zero_copy = match parent_infos {
+            [info] if is_zero_copy_plan_chunk(info) => {
+                require!(info.is_writable, VestingError::PlanChunkMismatch);
+                let zero_copy_plans = AccountLoader::<ZeroCopyPlanChunk>::try_from(info)?;
+                require_keys_eq!(
+                    zero_copy_plans.load()?.vesting_account,
+                    allocation.parent_vesting,
+                    VestingError::PlanChunkMismatch
+                );
+                Some(zero_copy_plans)                                  // Parent migrated: pages in the ledger are gone
+            }
+            _ => None,
+        };
+        let parent_page_infos = if parent_zero_copy.is_some() { &[] } else { parent_infos };
+        let mut parent_pages = Vec::with_capacity(parent_page_infos.len());
+        for (i, info) in parent_page_infos.iter().enumerate() {
+            require!(
+                info.is_writable && !parent_page_infos[..i].iter().any(|other| other.key == info.key),
+                VestingError::PlanChunkMismatch
+            );
+            let page = Account::<VestingPlanChunk>::try_from(info)?;
+            require_keys_eq!(page.vesting_account, allocation.parent_vesting, VestingError::PlanChunkMismatch);
+            parent_pages.push(page);
+        }
+
+        for (entry, unpaid) in allocation_refunds(&allocation.entries, &child_plans) {
+            if let Some(zero_copy_plans) = &parent_zero_copy {
+                let mut zero_copy_plans = zero_copy_plans.load_mut()?;
+                let mut plans = zero_copy_plans.to_plans();
+                restore_parent_plan(&mut plans, entry.release_time, unpaid)?;
+                zero_copy_plans.set_plans(&plans)?;
+                continue;
+            }
+            let parent_page = parent_pages
+                .iter_mut()
+                .find(|p| p.key() == entry.parent_plan_chunk)
+                .ok_or(VestingError::PlanChunkMismatch)?;
+            restore_parent_plan(&mut parent_page.plans, entry.release_time, unpaid)?;
+        }
+
+        child_store.close(&ctx.accounts.admin.to_account_info())?;    // Return plan storage rent to admin
+        for page in &parent_pages {
+            page.exit(ctx.program_id)?;                                // Persist the credited parent plans
+        }
+
+        let returned_amount = ctx.accounts.beneficiary_vault.amount; // Drain everything the child vault holds
+        if returned_amount > 0 {
+            transfer_from_vault(
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.beneficiary_vault.to_account_info(),
+                ctx.accounts.parent_vault.to_account_info(),
+                ctx.accounts.vault_authority.to_account_info(),
+                &ctx.accounts.admin_config.seed_admin,
+                &ctx.accounts.token_vault.key(),
+                ctx.bumps.vault_authority,
+                returned_amount,
+            )?;
+        }
+        close_vault(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.beneficiary_vault.to_account_info(),
+            ctx.accounts.admin.to_account_info(),          // Vault rent back to admin
+            ctx.accounts.vault_authority.to_account_info(),
+            &ctx.accounts.admin_config.seed_admin,
+            &ctx.accounts.token_vault.key(),
+            ctx.bumps.vault_authority,
+        )?;
+
+        emit!(ChildVestingRevoked {
+            child_vesting: vesting_account.key(),
+            parent_vesting: allocation.parent_vesting,
+            returned_amount,
+        });
+        Ok(())                                                         // vesting_account and allocation are closed by the account annotations
+    }
+
+    // First step of an admin handover (deployer or current admin); Pubkey::default() withdraws the proposal
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+        let proposer = ctx.accounts.proposer.key();
+        let admin_config = &mut ctx.accounts.admin_config;
+        require!(
+            proposer == admin_config.admin || proposer == ctx.accounts.deployer_admin.deployer,
+            VestingError::Unauthorized
+        );
+        consume_approval(
+            &ctx.accounts.approval_config,
+            ctx.accounts.proposal.as_deref_mut(),
+            &ProposalOperation::ProposeAdmin { new_admin },
+        )?;
+        admin_config.pending_admin = new_admin;
+
+        emit!(AdminProposed {
+            admin: admin_config.admin,
+            pending_admin: new_admin,
+            proposer,
+        });
+        Ok(())
+    }
+
+    // Second step: the proposed admin takes over; AdminConfig keeps its address
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        let admin_config = &mut ctx.accounts.admin_config;
+        let previous_admin = admin_config.admin;
+        admin_config.admin = admin_config.pending_admin;
+        admin_config.pending_admin = Pubkey::default();
+
+        emit!(AdminChanged {
+            previous_admin,
+            new_admin: admin_config.admin,
+        });
+        Ok(())
+    }
+
+    // Turn on M-of-N approvals for sensitive operations (admin, once; later changes need a proposal)
+    pub fn init_approval_config(ctx: Context<InitApprovalConfig>, signers: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        check_signer_set(&signers, threshold)?;
+        let approval_config = &mut ctx.accounts.approval_config;
+        approval_config.signers = signers;
+        approval_config.threshold = threshold;
+        Ok(())
+    }
+
+    // Replace the signer set/threshold through an approved ProposalOperation::SetApprovalConfig
+    pub fn update_approval_config(ctx: Context<UpdateApprovalConfig>, signers: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        check_signer_set(&signers, threshold)?;
+        consume_approval(
+            &ctx.accounts.approval_config.to_account_info(),
+            Some(&mut ctx.accounts.proposal),
+            &ProposalOperation::SetApprovalConfig {
+                signers: signers.clone(),
+                threshold,
+            },
+        )?;
+        let approval_config = &mut ctx.accounts.approval_config;
+        approval_config.signers = signers;
+        approval_config.threshold = threshold;
+        Ok(())
+    }
+
+    // Stage a sensitive operation; the proposer's approval is counted right away
+    pub fn create_proposal(ctx: Context<CreateProposal>, operation: ProposalOperation) -> Result<()> {
+        let approval_config = &mut ctx.accounts.approval_config;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.id = approval_config.proposal_count;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.operation = operation;
+        proposal.approvals = vec![ctx.accounts.proposer.key()];
+        proposal.executed = false;
+
+        approval_config.proposal_count = approval_config
+            .proposal_count
+            .checked_add(1)
+            .ok_or(VestingError::Overflow)?;
+
+        emit!(ProposalCreated {
+            proposal: proposal.key(),
+            id: proposal.id,
+            proposer: proposal.proposer,
+        });
+        Ok(())
+    }
+
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+        let approver = ctx.accounts.approver.key();
+        let approval_config = &ctx.accounts.approval_config;
+        let proposal = &mut ctx.accounts.proposal;
+        require!(!proposal.executed, VestingError::ProposalAlreadyExecuted);
+        require!(approval_config.signers.contains(&approver), VestingError::Unauthorized); // Current signer set
+        require!(!proposal.approvals.contains(&approver), VestingError::AlreadyApproved);
+        proposal.approvals.retain(|a| approval_config.signers.contains(a)); // Drop approvals of removed signers
+        proposal.approvals.push(approver);
+
+        emit!(ProposalApproved {
+            proposal: proposal.key(),
+            approver,
+            approvals: approval_config.approval_count(proposal) as u8,
+            threshold: approval_config.threshold,
+        });
+        Ok(())
+    }
+
+    // Withdraw a proposal (or clean up an executed one); rent goes back to the proposer
+    pub fn close_proposal(_ctx: Context<CloseProposal>) -> Result<()> {
+        Ok(())                                                         // Closed by the account annotation
+    }
+
+    // Upgrade the AdminConfig written before admin handover existed (deployer only)
+    pub fn migrate_admin_config(ctx: Context<MigrateAdminConfig>) -> Result<()> {
+        require!(
+            ctx.accounts.deployer_admin.deployer == ctx.accounts.deployer.key(),
+            VestingError::NotDeployAdmin
+        );
+        let info = ctx.accounts.admin_config.to_account_info();
+        require!(info.data_len() == LEGACY_ADMIN_CONFIG_SPACE, VestingError::InvalidParameters);
+
+        let legacy = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data[..DISCRIMINATOR_SIZE] == *AdminConfig::DISCRIMINATOR,
+                VestingError::InvalidParameters
+            );
+            LegacyAdminConfig::deserialize(&mut &data[DISCRIMINATOR_SIZE..])?
+        };
+
+        grow_account(
+            &info,
+            &ctx.accounts.deployer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            ADMIN_CONFIG_SPACE,
+        )?;
+
+        let migrated = AdminConfig {
+            admin: legacy.admin,
+            pending_admin: Pubkey::default(),
+            seed_admin: legacy.admin,                      // Existing vaults were derived from this admin
+        };
+        let mut data = info.try_borrow_mut_data()?;
+        migrated.try_serialize(&mut &mut data[..])?;
+        Ok(())
+    }
+
+    pub fn remove_admin(ctx: Context<RemoveAdmin>) -> Result<()> {    // Remove admin (deployer only)
+        require!(
+            ctx.accounts.deployer_admin.deployer == ctx.accounts.deployer.key(), // Check deployer
+            VestingError::NotDeployAdmin
+        );
+        consume_approval(                                              // remove_admin + initialize replaces the admin
+            &ctx.accounts.approval_config,
+            ctx.accounts.proposal.as_deref_mut(),
+            &ProposalOperation::RemoveAdmin,
+        )?;
+
+        let admin_config = &mut ctx.accounts.admin_config;
+        admin_config.admin = Pubkey::default();                        // initialize designates the next admin
+        admin_config.pending_admin = Pubkey::default();
+        Ok(())                                                         // seed_admin stays: existing vault PDAs derive from it
+    }
+
+    pub fn init_token_info(ctx: Context<InitTokenInfo>, args: TokenInfoArgs) -> Result<()> { // Register token metadata
+        let token_info = &mut ctx.accounts.token_info;                 // TokenInfo PDA
+
+        token_info.token_name = args.token_name;                       // Set name/symbol/total supply/mint/minting wallet
+        token_info.token_symbol = args.token_symbol;
+        token_info.total_supply = args.total_supply;
+        token_info.token_mint = args.token_mint;
+        token_info.mint_wallet_address = args.mint_wallet_address;
+
+        Ok(())
+    }
+
+}
+
+// Find the plan scheduled at `vesting_time` and make sure it can be released now
+fn find_matured_plan(plans: &mut [YearlyPlan], vesting_time: i64, now: i64) -> Result<&mut YearlyPlan> {
+    let plan = plans
+        .iter_mut()
+        .find(|p| p.release_time == vesting_time)              // Find a plan that matches the request time
+        .ok_or(VestingError::InvalidParameters)?;              // Error if not found
+
+    require!(plan.release_time <= now, VestingError::VestingNotReached); // Has the time for that plan passed?
+    require!(!plan.released, VestingError::AlreadyReleased);   // Cannot proceed if the plan has already been released
+    require!(plan.remaining_amount() > 0, VestingError::NoTokensToRelease); // Nothing left to transfer for this plan
+
+    Ok(plan)
+}
+
+// Settle everything due now: matured plans for Plan vestings, the unlocked remainder for Linear vestings
+fn release_due(
+    vesting_account: &Account<VestingAccount>,
+    store: &mut PlanStore,
+    now: i64,
+) -> Result<(u64, Vec<i64>)> {
+    vesting_account.check_cliff(now)?;                     // Everything accrued before the cliff unlocks at once
+    let (amount, release_times) = match vesting_account.kind {
+        VestingKind::Plan => match store {
+            PlanStore::Pages(pages) => {
+                require!(!pages.is_empty(), VestingError::InvalidParameters); // Plan vestings need their chunks
+                let mut amount: u64 = 0;
+                let mut release_times = Vec::new();
+                for page in pages.iter_mut() {
+                    require_keys_eq!(page.vesting_account, vesting_account.key(), VestingError::InvalidParameters);
+                    let (page_amount, page_times) = release_matured_plans(&mut page.plans, now)?;
+                    amount = amount.checked_add(page_amount).ok_or(VestingError::Overflow)?;
+                    release_times.extend(page_times);
+                }
+                (amount, release_times)
+            }
+            PlanStore::ZeroCopy(zero_copy_plans) => {
+                let mut zero_copy_plans = zero_copy_plans.load_mut()?;
+                require_keys_eq!(zero_copy_plans.vesting_account, vesting_account.key(), VestingError::InvalidParameters);
+                let mut amount: u64 = 0;
+                let mut release_times = Vec::new();
+                for slot in zero_copy_plans.plans_mut() {  // Only matured slots are converted and written back
+                    let mut plan = YearlyPlan::from(&*slot);
+                    let (plan_amount, plan_times) = release_matured_plans(std::slice::from_mut(&mut plan), now)?;
+                    if plan_times.is_empty() {
+                        continue;
+                    }
+                    *slot = ZeroCopyPlan::from(&plan);
+                    amount = amount.checked_add(plan_amount).ok_or(VestingError::Overflow)?;
+                    release_times.extend(plan_times);
+                }
+                (amount, release_times)
+            }
+        },
+        VestingKind::Linear => (vesting_account.releasable_linear_amount(now)?, Vec::new()), // No discrete plans are settled
+    };
+    require!(amount > 0, VestingError::NoTokensToRelease); // Nothing has matured yet
+
+    Ok((amount, release_times))
+}
+
+// What release_due would pay out at `now`, without marking anything
+fn matured_amount(vesting_account: &VestingAccount, plans: &[YearlyPlan], now: i64) -> Result<u64> {
+    vesting_account.check_cliff(now)?;
+    let amount = match vesting_account.kind {
+        VestingKind::Plan => plans
+            .iter()
+            .filter(|p| !p.released && p.release_time <= now)
+            .try_fold(0u64, |sum, p| sum.checked_add(p.remaining_amount()))
+            .ok_or(VestingError::Overflow)?,
+        VestingKind::Linear => vesting_account.releasable_linear_amount(now)?,
+    };
+    require!(amount > 0, VestingError::NoTokensToRelease);
+    Ok(amount)
+}
+
+// Mark every matured, unreleased plan as released; returns the total amount and the settled release times
+fn release_matured_plans(plans: &mut [YearlyPlan], now: i64) -> Result<(u64, Vec<i64>)> {
+    let mut amount: u64 = 0;
+    let mut release_times = Vec::new();
+    for plan in plans.iter_mut().filter(|p| !p.released && p.release_time <= now) {
+        amount = amount.checked_add(plan.release_remaining()).ok_or(VestingError::Overflow)?;
+        release_times.push(plan.release_time);
+    }
+
+    Ok((amount, release_times))
+}
+
+// Load every plan chunk page of a vesting; each of the plan_chunk_count pages must be passed exactly once
+fn load_plan_pages<'info>(
+    vesting_account: &Account<VestingAccount>,
+    infos: &'info [AccountInfo<'info>],
+    writable: bool,
+) -> Result<Vec<Account<'info, VestingPlanChunk>>> {
+    require!(
+        infos.len() == vesting_account.plan_chunk_count as usize,
+        VestingError::PlanChunkMismatch
+    );
+
+    let mut pages = Vec::with_capacity(infos.len());
+    for (i, info) in infos.iter().enumerate() {
+        require!(
+            !infos[..i].iter().any(|other| other.key == info.key), // A duplicate would be released twice
+            VestingError::PlanChunkMismatch
+        );
+        require!(!writable || info.is_writable, VestingError::PlanChunkMismatch);
+
+        let page = Account::<VestingPlanChunk>::try_from(info)?;
+        require_keys_eq!(page.vesting_account, vesting_account.key(), VestingError::PlanChunkMismatch);
+        pages.push(page);
+    }
+    Ok(pages)
+}
+
+// Plan storage of a vesting: its Borsh pages, or the zero-copy chunk after migrate_to_zero_copy_plans
+enum PlanStore<'info> {
+    Pages(Vec<Account<'info, VestingPlanChunk>>),
+    ZeroCopy(AccountLoader<'info, ZeroCopyPlanChunk>),
+}
+
+impl<'info> PlanStore<'info> {
+    // Load the plan storage from exactly `infos`: every page, or the single zero-copy chunk of a migrated vesting
+    fn load(
+        vesting_account: &Account<VestingAccount>,
+        infos: &'info [AccountInfo<'info>],
+        writable: bool,
+    ) -> Result<Self> {
+        let (store, used) = Self::load_prefix(vesting_account, infos, writable)?;
+        require!(used == infos.len(), VestingError::PlanChunkMismatch);
+        Ok(store)
+    }
+
+    // Like `load`, but the storage may be followed by other accounts; also returns how many accounts it used
+    fn load_prefix(
+        vesting_account: &Account<VestingAccount>,
+        infos: &'info [AccountInfo<'info>],
+        writable: bool,
+    ) -> Result<(Self, usize)> {
+        if vesting_account.plan_chunk_count == 0 && infos.first().is_some_and(is_zero_copy_plan_chunk) {
+            let info = &infos[0];
+            require!(!writable || info.is_writable, VestingError::PlanChunkMismatch);
+            let zero_copy_plans = AccountLoader::<ZeroCopyPlanChunk>::try_from(info)?;
+            require_keys_eq!(
+                zero_copy_plans.load()?.vesting_account,
+                vesting_account.key(),
+                VestingError::PlanChunkMismatch
+            );
+            return Ok((PlanStore::ZeroCopy(zero_copy_plans), 1));
+        }
+
+        let count = vesting_account.plan_chunk_count as usize;
+        require!(infos.len() >= count, VestingError::PlanChunkMismatch);
+        let pages = load_plan_pages(vesting_account, &infos[..count], writable)?;
+        Ok((PlanStore::Pages(pages), count))
+    }
+
+    // Every plan, in storage order
+    fn plans(&self) -> Result<Vec<YearlyPlan>> {
+        Ok(match self {
+            PlanStore::Pages(pages) => pages.iter().flat_map(|p| p.plans.iter().cloned()).collect(),
+            PlanStore::ZeroCopy(zero_copy_plans) => zero_copy_plans.load()?.to_plans(),
+        })
+    }
+
+    // Persist released flags (zero-copy slots are written in place)
+    fn exit(&self, program_id: &Pubkey) -> Result<()> {
+        if let PlanStore::Pages(pages) = self {
+            for page in pages {
+                page.exit(program_id)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Close the storage, returning its rent to `destination`
+    fn close(&self, destination: &AccountInfo<'info>) -> Result<()> {
+        match self {
+            PlanStore::Pages(pages) => {
+                for page in pages {
+                    close_program_account(&page.to_account_info(), destination)?;
+                }
+            }
+            PlanStore::ZeroCopy(zero_copy_plans) => {
+                close_program_account(&zero_copy_plans.to_account_info(), destination)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// One account of a parent's plan storage named by a child's ledger: a parent page, or its zero-copy chunk once
+// migrated. Read by hand, as handlers only hold a short borrow of the account.
+fn read_parent_plans(info: &AccountInfo, parent_vesting: &Pubkey) -> Result<Vec<YearlyPlan>> {
+    let (vesting_account, plans) = if is_zero_copy_plan_chunk(info) {
+        let data = info.try_borrow_data()?;
+        let zero_copy_plans = zero_copy_plan_chunk(&data)?;
+        (zero_copy_plans.vesting_account, zero_copy_plans.to_plans())
+    } else {
+        require_keys_eq!(*info.owner, crate::ID, VestingError::PlanChunkMismatch);
+        let page = VestingPlanChunk::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        (page.vesting_account, page.plans)
+    };
+    require_keys_eq!(vesting_account, *parent_vesting, VestingError::PlanChunkMismatch);
+    Ok(plans)
+}
+
+// Edit the plans of a parent storage account in place (see read_parent_plans); the plan count must not change
+fn edit_parent_plans(
+    info: &AccountInfo,
+    parent_vesting: &Pubkey,
+    edit: impl FnOnce(&mut [YearlyPlan]) -> Result<()>,
+) -> Result<()> {
+    require!(info.is_writable, VestingError::PlanChunkMismatch);
+    let mut plans = read_parent_plans(info, parent_vesting)?;
+    edit(&mut plans)?;
+    if is_zero_copy_plan_chunk(info) {
+        let mut data = info.try_borrow_mut_data()?;
+        zero_copy_plan_chunk_mut(&mut data)?.set_plans(&plans)
+    } else {
+        let page = VestingPlanChunk { vesting_account: *parent_vesting, plans };
+        page.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])
+    }
+}
+
+fn zero_copy_plan_chunk(data: &[u8]) -> Result<&ZeroCopyPlanChunk> {
+    data.get(DISCRIMINATOR_SIZE..DISCRIMINATOR_SIZE + std::mem::size_of::<ZeroCopyPlanChunk>())
+        .and_then(|bytes| bytemuck::try_from_bytes(bytes).ok())
+        .ok_or_else(|| ErrorCode::AccountDidNotDeserialize.into())
+}
+
+fn zero_copy_plan_chunk_mut(data: &mut [u8]) -> Result<&mut ZeroCopyPlanChunk> {
+    data.get_mut(DISCRIMINATOR_SIZE..DISCRIMINATOR_SIZE + std::mem::size_of::<ZeroCopyPlanChunk>())
+        .and_then(|bytes| bytemuck::try_from_bytes_mut(bytes).ok())
+        .ok_or_else(|| ErrorCode::AccountDidNotDeserialize.into())
+}
+
+fn is_zero_copy_plan_chunk(info: &AccountInfo) -> bool {
+    *info.owner == crate::ID
+        && info
+            .try_borrow_data()
+            .is_ok_and(|data| data.len() >= DISCRIMINATOR_SIZE && data[..DISCRIMINATOR_SIZE] == *ZeroCopyPlanChunk::DISCRIMINATOR)
+}
+
+// Every migrated ledger entry must name a real tranche on one of the parent's pages
+fn check_allocation_entries(entries: &[AllocationEntry], parent_pages: &[(Pubkey, Vec<YearlyPlan>)]) -> Result<()> {
+    for entry in entries {
+        require!(entry.amount > 0, VestingError::InvalidParameters);
+        let (_, plans) = parent_pages
+            .iter()
+            .find(|(key, _)| *key == entry.parent_plan_chunk)
+            .ok_or(VestingError::PlanChunkMismatch)?;
+        require!(
+            plans.iter().any(|p| p.release_time == entry.release_time),
+            VestingError::UnmatchedAllocation
+        );
+    }
+    Ok(())
+}
+
+// What revoking a child gives back to each parent tranche: the child's unpaid part of it (paid-out tranches stay spent)
+fn allocation_refunds<'a>(entries: &'a [AllocationEntry], child_plans: &[YearlyPlan]) -> Vec<(&'a AllocationEntry, u64)> {
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let unpaid = child_plans
+                .iter()
+                .find(|p| p.release_time == entry.release_time)
+                .map_or(0, |p| p.remaining_amount().min(entry.amount));
+            (unpaid > 0).then_some((entry, unpaid))
+        })
+        .collect()
+}
+
+// Credit `amount` back to the parent plan at `release_time`
+fn restore_parent_plan(plans: &mut [YearlyPlan], release_time: i64, amount: u64) -> Result<()> {
+    plans
+        .iter_mut()
+        .find(|p| p.release_time == release_time)
+        .ok_or(VestingError::UnmatchedAllocation)?
+        .restore(amount)
+}
+
+// Child plans mirror their allocation ledger; editing them would leave parent tranches unaccounted for
+fn check_plans_unallocated(vesting_account: &VestingAccount) -> Result<()> {
+    require!(
+        vesting_account.token_vault == vesting_account.parent_vault, // Child vestings: revoke instead
+        VestingError::AllocatedPlansLocked
+    );
+    Ok(())
+}
+
+// Append `plans` to page `chunk_index`, funding a child vesting's plans from its parent first
+#[allow(clippy::too_many_arguments)]
+fn append_plans<'info>(
+    vesting_account: &mut Account<'info, VestingAccount>,
+    chunk: &mut Account<'info, VestingPlanChunk>,
+    chunk_index: u16,
+    plans: Vec<YearlyPlan>,
+    parent_plan_chunk: Option<&AccountInfo<'info>>,
+    allocation: Option<&mut Account<'info, PlanAllocation>>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    require!(
+        vesting_account.kind == VestingKind::Plan,     // Linear vestings have no plans
+        VestingError::InvalidParameters
+    );
+
+    require!(
+        plans.iter().all(|p| p.released_amount <= p.amount),        // Cannot have paid more than the plan
+        VestingError::InvalidParameters
+    );
+
+    let deduct = vesting_account.token_vault.key()
+        != vesting_account.parent_vault.key();                      // Perform parent deduction if different from parent vault
+
+    if deduct {
+        let allocation = allocation.ok_or(VestingError::AllocationNotFound)?; // Child vestings carry a ledger
+        let parent_info = parent_plan_chunk.ok_or(VestingError::ParentPlanNotFound)?; // Check for parent plan existence
+        let parent_vesting = allocation.parent_vesting;                // Only the recorded parent can fund this child
+        edit_parent_plans(parent_info, &parent_vesting, |parent_plans| {
+            deduct_parent_plans(parent_plans, &plans, parent_info.key(), &mut allocation.entries)
+        })?;
+
+        grow_account(
+            &allocation.to_account_info(),
+            payer,
+            system_program,
+            plan_allocation_space(allocation.entries.len()),
+        )?;
+    }
+
+    if chunk.vesting_account == Pubkey::default() {
+        // Fresh page: pages are numbered contiguously from 0
+        require!(chunk_index == vesting_account.plan_chunk_count, VestingError::PlanChunkMismatch);
+        vesting_account.plan_chunk_count = vesting_account
+            .plan_chunk_count
+            .checked_add(1)
+            .ok_or(VestingError::Overflow)?;
+    }
+    require!(chunk.plans.len() + plans.len() <= MAX_PLANS, VestingError::InsufficientSpace); // Page capacity; open the next page instead
+
+    grow_account(
+        &chunk.to_account_info(),
+        payer,
+        system_program,
+        plan_chunk_space(chunk.plans.len() + plans.len()),         // Admin pays rent only for the plans stored
+    )?;
+
+    chunk.vesting_account = vesting_account.key(); // Indicate the owning vesting account
+    chunk.plans.extend(plans);                                   // Add plans
+    Ok(())
+}
+
+// Each unreleased child plan is funded by the parent plan with the same release_time; `source` is the parent
+// page (or zero-copy chunk) the ledger entries point at
+fn deduct_parent_plans(
+    parent_plans: &mut [YearlyPlan],
+    plans: &[YearlyPlan],
+    source: Pubkey,
+    entries: &mut Vec<AllocationEntry>,
+) -> Result<()> {
+    for user_plan in plans.iter().filter(|p| !p.released && p.remaining_amount() > 0) {
+        let parent_plan = parent_plans
+            .iter_mut()
+            .find(|p| p.release_time == user_plan.release_time && !p.released)
+            .ok_or(VestingError::UnmatchedAllocation)?;
+        require!(
+            parent_plan.remaining_amount() >= user_plan.remaining_amount(),
+            VestingError::InsufficientAmount
+        );
+
+        parent_plan.amount = parent_plan
+            .amount
+            .checked_sub(user_plan.remaining_amount())
+            .ok_or(VestingError::Overflow)?;                       // Deduct amount from parent plan
+        entries.push(AllocationEntry {
+            release_time: user_plan.release_time,
+            amount: user_plan.remaining_amount(),
+            parent_plan_chunk: source,
+        });
+    }
+    Ok(())
+}
+
+// Apply a patch to a zero-copy chunk and re-check the whole schedule
+fn patch_zero_copy(
+    zero_copy_plans: &AccountLoader<ZeroCopyPlanChunk>,
+    vesting_account: &VestingAccount,
+    patch: &PlanPatch,
+    validation: ScheduleValidation,
+) -> Result<()> {
+    validation.check_patch()?;
+    let mut zero_copy_plans = zero_copy_plans.load_mut()?;
+    let mut plans = zero_copy_plans.to_plans();
+    patch.apply(&mut plans)?;
+    check_schedule(vesting_account, &plans, &[], &[], validation)?;
+    zero_copy_plans.set_plans(&plans)
+}
+
+// Replace a page's plans; paid plans must survive unchanged
+fn replace_plans(plan_chunk: &mut VestingPlanChunk, plans: Vec<YearlyPlan>, allow_amount_change: bool) -> Result<()> {
+    require!(
+        plans.iter().all(|p| p.released_amount <= p.amount),
+        VestingError::InvalidParameters
+    );
+    require!(plans.len() <= MAX_PLANS, VestingError::InsufficientSpace); // Page capacity
+
+    check_paid_plans_kept(&plan_chunk.plans, &plans)?;                 // Paid plans survive unchanged
+    require!(
+        allow_amount_change || unreleased_total(&plan_chunk.plans)? == unreleased_total(&plans)?,
+        VestingError::UnreleasedAmountChanged
+    );
+
+    plan_chunk.plans.clear();                                         // Delete existing
+    plan_chunk.plans.extend(plans);                                   // Refill with new
+    Ok(())
+}
+
+// With a plan timelock in force, a started vesting's pages only change through queue/execute_plan_change
+fn check_plan_timelock(plan_timelock: &AccountInfo, vesting_account: &VestingAccount) -> Result<()> {
+    if plan_timelock.data_is_empty() {
+        return Ok(());                                               // No timelock configured
+    }
+    require_keys_eq!(*plan_timelock.owner, crate::ID, VestingError::Unauthorized);
+    let timelock = PlanTimelock::try_deserialize(&mut &plan_timelock.try_borrow_data()?[..])?;
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        timelock.delay_at(now) == 0 || now < vesting_account.start_time, // Schedules may still be set up before start
+        VestingError::TimelockRequired
+    );
+    Ok(())
+}
+
+// With M-of-N approvals configured, `operation` only runs through a fully approved, unused proposal for exactly it
+fn consume_approval(
+    approval_config: &AccountInfo,
+    proposal: Option<&mut Account<Proposal>>,
+    operation: &ProposalOperation,
+) -> Result<()> {
+    if approval_config.data_is_empty() {
+        return Ok(());                                               // Approvals not configured: single key as before
+    }
+    require_keys_eq!(*approval_config.owner, crate::ID, VestingError::Unauthorized);
+    let approval_config = ApprovalConfig::try_deserialize(&mut &approval_config.try_borrow_data()?[..])?;
+
+    let proposal = proposal.ok_or(VestingError::ApprovalRequired)?;
+    approval_config.check_approved(proposal, operation)?;
+    proposal.executed = true;
+
+    emit!(ProposalExecuted {
+        proposal: proposal.key(),
+        id: proposal.id,
+    });
+    Ok(())
+}
+
+fn check_signer_set(signers: &[Pubkey], threshold: u8) -> Result<()> {
+    require!(signers.len() <= MAX_APPROVERS, VestingError::InvalidParameters);
+    require!(threshold > 0 && threshold as usize <= signers.len(), VestingError::InvalidParameters);
+    require!(
+        signers.iter().enumerate().all(|(i, s)| !signers[..i].contains(s)), // No duplicate approvers
+        VestingError::InvalidParameters
+    );
+    Ok(())
+}
+
+// Check plan chunk page `chunk_index` after a change, together with the vesting's other pages (in page order)
+fn validate_schedule<'info>(
+    vesting_account: &Account<VestingAccount>,
+    chunk_index: u16,
+    plans: &[YearlyPlan],
+    siblings: &'info [AccountInfo<'info>],
+    validation: ScheduleValidation,
+) -> Result<()> {
+    if validation == ScheduleValidation::Skip {
+        return Ok(());                                               // Legacy import: take the data as is
+    }
+    require!(
+        siblings.len() + 1 == vesting_account.plan_chunk_count as usize, // Every other page exactly once
+        VestingError::PlanChunkMismatch
+    );
+
+    let (mut earlier, mut later) = (Vec::new(), Vec::new());
+    for (i, info) in siblings.iter().enumerate() {
+        let index = if i < chunk_index as usize { i } else { i + 1 }; // Skip the page being checked
+        require_keys_eq!(
+            *info.key,
+            plan_page_address(&vesting_account.key(), index as u16),
+            VestingError::PlanChunkMismatch
+        );
+        let page = Account::<VestingPlanChunk>::try_from(info)?;
+        require_keys_eq!(page.vesting_account, vesting_account.key(), VestingError::PlanChunkMismatch);
+        if index < chunk_index as usize {
+            earlier.extend(page.plans.iter().cloned());
+        } else {
+            later.extend(page.plans.iter().cloned());
+        }
+    }
+    check_schedule(vesting_account, plans, &earlier, &later, validation)
+}
+
+// Address of page `index` of a vesting's plan chunk
+fn plan_page_address(vesting_account: &Pubkey, index: u16) -> Pubkey {
+    Pubkey::find_program_address(&[b"plans", vesting_account.as_ref(), &index.to_le_bytes()], &crate::ID).0
+}
+
+// Check edited `plans` against the vesting; `earlier`/`later` are the untouched plans of the pages before/after it
+fn check_schedule(
+    vesting_account: &VestingAccount,
+    plans: &[YearlyPlan],
+    earlier: &[YearlyPlan],
+    later: &[YearlyPlan],
+    validation: ScheduleValidation,
+) -> Result<()> {
+    if validation == ScheduleValidation::Skip {
+        return Ok(());                                               // Legacy import: take the data as is
+    }
+
+    let mut release_times = Vec::new();
+    let mut scheduled: u64 = 0;                                      // Unpaid amount across all pages
+    for (i, plan) in plans.iter().enumerate() {
+        require!(plan.amount > 0, VestingError::ZeroPlanAmount);
+        require!(
+            plan.release_time >= vesting_account.start_time && plan.release_time <= vesting_account.end_time,
+            VestingError::PlanOutsideVestingPeriod
+        );
+        if let Some(prev) = i.checked_sub(1).map(|j| &plans[j]) {
+            require!(prev.release_time != plan.release_time, VestingError::DuplicatePlanTime);
+            require!(prev.release_time < plan.release_time, VestingError::PlansNotSorted);
+        }
+        release_times.push(plan.release_time);
+        scheduled = scheduled.checked_add(plan.remaining_amount()).ok_or(VestingError::Overflow)?;
+    }
+    for plan in earlier.iter().chain(later) {
+        release_times.push(plan.release_time);
+        scheduled = scheduled.checked_add(plan.remaining_amount()).ok_or(VestingError::Overflow)?;
+    }
+
+    release_times.sort_unstable();                                   // Release times are unique across pages too
+    require!(
+        release_times.windows(2).all(|w| w[0] != w[1]),
+        VestingError::DuplicatePlanTime
+    );
+
+    // Pages run in index order: every plan of an earlier page comes before this page, and this page before later ones
+    let last_earlier = earlier.iter().map(|p| p.release_time).max();
+    let first_later = later.iter().map(|p| p.release_time).min();
+    let first = plans.first().map(|p| p.release_time).or(first_later);
+    let last = plans.last().map(|p| p.release_time).or(last_earlier);
+    require!(
+        !matches!((last_earlier, first), (Some(a), Some(b)) if a > b)
+            && !matches!((last, first_later), (Some(a), Some(b)) if a > b),
+        VestingError::PlansNotSorted
+    );
+
+    let unreleased = vesting_account
+        .total_amount
+        .checked_sub(vesting_account.released_amount)
+        .ok_or(VestingError::Overflow)?;
+    match validation {
+        ScheduleValidation::Strict => require!(scheduled == unreleased, VestingError::ScheduleAmountMismatch),
+        ScheduleValidation::Incremental => require!(scheduled <= unreleased, VestingError::ScheduleAmountMismatch),
+        ScheduleValidation::Skip => {}
+    }
+    Ok(())
+}
+
+// Plans that have paid out anything may not be edited, removed or made up
+fn check_paid_plans_kept(old: &[YearlyPlan], new: &[YearlyPlan]) -> Result<()> {
+    for plan in old.iter().filter(|p| p.has_payouts()) {
+        require!(new.contains(plan), VestingError::ReleasedPlanModified);
+    }
+    for plan in new.iter().filter(|p| p.has_payouts()) {
+        require!(old.contains(plan), VestingError::ReleasedPlanModified);
+    }
+    Ok(())
+}
+
+// Plan at `index` that has not paid out anything yet
+fn unpaid_plan(plans: &mut [YearlyPlan], index: u16) -> Result<&mut YearlyPlan> {
+    let plan = plans.get_mut(index as usize).ok_or(VestingError::PlanIndexOutOfRange)?;
+    require!(!plan.has_payouts(), VestingError::ReleasedPlanModified);
+    Ok(plan)
+}
+
+// Sum of what the plans still have to pay out
+fn unreleased_total(plans: &[YearlyPlan]) -> Result<u64> {
+    plans
+        .iter()
+        .try_fold(0u64, |sum, p| sum.checked_add(p.remaining_amount()))
+        .ok_or_else(|| VestingError::Overflow.into())
+}
+
+// Add calendar months to a unix timestamp, keeping the time of day and clamping the day (Jan 31 + 1 = Feb 28/29)
+fn add_months(time: i64, months: u32) -> Result<i64> {
+    let days = time.div_euclid(86_400);
+    let seconds = time.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+
+    let index = year
+        .checked_mul(12)
+        .and_then(|m| m.checked_add(month - 1 + months as i64))
+        .ok_or(VestingError::Overflow)?;
+    let (year, month) = (index.div_euclid(12), index.rem_euclid(12) + 1);
+    let day = day.min(days_in_month(year, month));
+
+    days_from_civil(year, month, day)
+        .checked_mul(86_400)
+        .and_then(|t| t.checked_add(seconds))
+        .ok_or_else(|| VestingError::Overflow.into())
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 for a proleptic Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// Inverse of days_from_civil: (year, month, day)
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+// Decode a vesting account still in the legacy layout (data including the discriminator) into the current layout
+fn migrated_vesting_account(data: &[u8]) -> Result<VestingAccount> {
+    require!(
+        data.len() == LEGACY_VESTING_ACCOUNT_SPACE,      // Only legacy-sized accounts need migration
+        VestingError::InvalidParameters
+    );
+    require!(
+        data[..DISCRIMINATOR_SIZE] == *VestingAccount::DISCRIMINATOR, // Must really be a VestingAccount
+        VestingError::InvalidParameters
+    );
+    let legacy = LegacyVestingAccount::deserialize(&mut &data[DISCRIMINATOR_SIZE..])?;
+
+    Ok(VestingAccount {
+        beneficiary: legacy.beneficiary,
+        total_amount: legacy.total_amount,
+        released_amount: legacy.released_amount,
+        start_time: legacy.start_time,
+        end_time: legacy.end_time,
+        last_release_time: legacy.last_release_time,
+        token_mint: legacy.token_mint,
+        token_vault: legacy.token_vault,
+        beneficiary_vault: legacy.beneficiary_vault,
+        category: legacy.category,
+        is_active: legacy.is_active,
+        destination_token_account: legacy.destination_token_account,
+        parent_vault: legacy.parent_vault,
+        kind: VestingKind::Plan,                       // Legacy vestings were always plan-based
+        cliff_time: 0,                                 // ... and had no cliff
+        destination_policy: DestinationPolicy::BeneficiaryOrMintWallet, // Behaviour before policies existed
+        plan_chunk_count: 0,                           // Set to 1 by migrate_plan_chunk
+    })
+}
+
+// Decode a legacy plan chunk (data including the discriminator) into page form with per-plan payouts
+fn migrated_plan_chunk(data: &[u8]) -> Result<VestingPlanChunk> {
+    require!(
+        data.len() == LEGACY_PLAN_CHUNK_SPACE,           // Only legacy-sized chunks need migration
+        VestingError::InvalidParameters
+    );
+    require!(
+        data[..DISCRIMINATOR_SIZE] == *VestingPlanChunk::DISCRIMINATOR,
+        VestingError::InvalidParameters
+    );
+    let legacy = LegacyVestingPlanChunk::deserialize(&mut &data[DISCRIMINATOR_SIZE..])?;
+
+    Ok(VestingPlanChunk {
+        vesting_account: legacy.vesting_account,
+        plans: legacy
+            .plans
+            .into_iter()
+            .map(|p| YearlyPlan {
+                release_time: p.release_time,
+                amount: p.amount,
+                released: p.released,
+                released_amount: if p.released { p.amount } else { 0 }, // Legacy releases were always full
+            })
+            .collect(),
+    })
+}
+
+// Move `tip` lamports from the tip pool to the cranker if the pool stays rent-exempt; returns the tip paid
+fn pay_crank_tip(pool_info: &AccountInfo, cranker: &AccountInfo, tip: u64, rent_floor: u64) -> Result<u64> {
+    if tip == 0 || pool_info.lamports() < rent_floor.saturating_add(tip) {
+        return Ok(0);                                                // Tips disabled or pool drained
+    }
+    **pool_info.try_borrow_mut_lamports()? -= tip;
+    **cranker.try_borrow_mut_lamports()? = cranker.lamports().checked_add(tip).ok_or(VestingError::Overflow)?;
+    Ok(tip)
+}
+
+// Close a program-owned account and send its rent to `destination`
+fn close_program_account<'info>(info: &AccountInfo<'info>, destination: &AccountInfo<'info>) -> Result<()> {
+    let lamports = info.lamports();
+    **destination.try_borrow_mut_lamports()? = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(VestingError::Overflow)?;
+    **info.try_borrow_mut_lamports()? = 0;
+
+    info.assign(&system_program::ID);
+    info.resize(0)?;
+    Ok(())
+}
+
+// Split batch_do_vesting's remaining_accounts into entries of BATCH_ENTRY_ACCOUNTS + extra accounts each
+fn batch_entries<'a, T>(accounts: &'a [T], extra_accounts: &[u8]) -> Result<Vec<&'a [T]>> {
+    require!(!extra_accounts.is_empty(), VestingError::InvalidParameters);
+    let mut rest = accounts;
+    let mut groups = Vec::with_capacity(extra_accounts.len());
+    for &extra in extra_accounts {
+        let len = BATCH_ENTRY_ACCOUNTS + extra as usize;
+        require!(rest.len() >= len, VestingError::InvalidParameters); // Only complete groups
+        let (group, tail) = rest.split_at(len);
+        groups.push(group);
+        rest = tail;
+    }
+    require!(rest.is_empty(), VestingError::InvalidParameters);      // No accounts left over
+    Ok(groups)
+}
+
+// A batch_do_vesting entry that passed every check
+struct BatchRelease<'info> {
+    vesting_account: Account<'info, VestingAccount>,
+    store: PlanStore<'info>,
+    origin_token_account: Account<'info, TokenAccount>,
+    destination_token_account: Account<'info, TokenAccount>,
+    amount: u64,
+}
+
+// Check one batch_do_vesting entry [vesting, origin vault, destination, plan storage..., allowlist?] without writing anything
+fn prepare_batch_entry<'info>(
+    group: &'info [AccountInfo<'info>],
+    accounts: &BatchDoVesting<'info>,
+    now: i64,
+) -> Result<BatchRelease<'info>> {
+    require!(
+        group[..BATCH_ENTRY_ACCOUNTS].iter().all(|info| info.is_writable),
+        VestingError::InvalidParameters
+    );
+    let vesting_account = Account::<VestingAccount>::try_from(&group[0])?;
+    let origin_token_account = Account::<TokenAccount>::try_from(&group[1])?;
+    let destination_token_account = Account::<TokenAccount>::try_from(&group[2])?;
+    let (store, used) = PlanStore::load_prefix(&vesting_account, &group[BATCH_ENTRY_ACCOUNTS..], true)?; // Every page, none for Linear
+    let allowlist = match &group[BATCH_ENTRY_ACCOUNTS + used..] {
+        [] => None,
+        [info] => {
+            let allowlist = Account::<DestinationAllowlist>::try_from(info)?;
+            require_keys_eq!(allowlist.vesting_account, vesting_account.key(), VestingError::InvalidParameters);
+            Some(allowlist)
+        }
+        _ => return err!(VestingError::InvalidParameters),
+    };
+
+    require!(vesting_account.is_active, VestingError::NotActive);
+    require_keys_eq!(vesting_account.token_mint, accounts.token_mint.key(), VestingError::InvalidMint);
+    require_keys_eq!(vesting_account.token_vault, accounts.token_vault.key(), VestingError::Unauthorized);
+    require_keys_eq!(origin_token_account.key(), vesting_account.beneficiary_vault, VestingError::Unauthorized);
+    require_keys_eq!(origin_token_account.owner, accounts.vault_authority.key(), VestingError::Unauthorized);
+    check_destination(
+        &destination_token_account,
+        &vesting_account,
+        &accounts.token_info.mint_wallet_address,
+        allowlist.as_ref(),
+    )?;
+    check_bulk_destination(&accounts.approval_config, &vesting_account, &destination_token_account.key())?;
+    require!(
+        !origin_token_account.is_frozen() && !destination_token_account.is_frozen(), // The transfer would fail
+        VestingError::TokenAccountFrozen
+    );
+
+    let amount = matured_amount(&vesting_account, &store.plans()?, now)?;
+    require!(origin_token_account.amount >= amount, VestingError::InsufficientAmount); // Skip rather than fail the transfer
+    (*vesting_account).clone().record_release(amount, now)?;             // Would the booking succeed?
+
+    Ok(BatchRelease {
+        vesting_account,
+        store,
+        origin_token_account,
+        destination_token_account,
+        amount,
+    })
+}
+
+// Pay out a checked batch_do_vesting entry
+fn release_batch_entry<'info>(
+    release: BatchRelease<'info>,
+    accounts: &BatchDoVesting<'info>,
+    bump: u8,
+    program_id: &Pubkey,
+    now: i64,
+) -> Result<u64> {
+    let BatchRelease {
+        mut vesting_account,
+        mut store,
+        origin_token_account,
+        destination_token_account,
+        amount,
+    } = release;
+
+    transfer_from_vault(
+        accounts.token_program.to_account_info(),
+        origin_token_account.to_account_info(),
+        destination_token_account.to_account_info(),
+        accounts.vault_authority.to_account_info(),
+        &accounts.admin_config.seed_admin,
+        &accounts.token_vault.key(),
+        bump,
+        amount,
+    )?;
+
+    let (released, release_times) = release_due(&vesting_account, &mut store, now)?;
+    require!(released == amount, VestingError::InvalidParameters);     // Same plans as checked
+    vesting_account.record_release(amount, now)?;
+    vesting_account.exit(program_id)?;                     // Later entries may read the same accounts
+    store.exit(program_id)?;
+
+    emit!(PlansReleased {
+        vesting_account: vesting_account.key(),
+        amount,
+        release_times,
+    });
+
+    Ok(amount)
+}
+
+// Bulk releases cannot carry a proposal per plan; with M-of-N approvals configured they may only pay the
+// beneficiary ATA, and other destinations go through do_vesting with an approved ProposalOperation::Release
+fn check_bulk_destination(
+    approval_config: &AccountInfo,
+    vesting_account: &VestingAccount,
+    destination: &Pubkey,
+) -> Result<()> {
+    if approval_config.data_is_empty() {
+        return Ok(());                                               // Approvals not configured: destination policy only
+    }
+    require_keys_eq!(
+        *destination,
+        get_associated_token_address(&vesting_account.beneficiary, &vesting_account.token_mint),
+        VestingError::ApprovalRequired
+    );
+    Ok(())
+}
+
+// Numeric code reported for a skipped batch entry
+fn error_code_of(err: &Error) -> u32 {
+    match err {
+        Error::AnchorError(e) => e.error_code_number,
+        Error::ProgramError(e) => match &e.program_error {
+            ProgramError::Custom(code) => *code,
+            builtin => (u64::from(builtin.clone()) >> 32) as u32, // Builtin errors are numbered in the upper half
+        },
+    }
+}
+
+// The destination must satisfy the vesting's destination policy
+fn check_destination(
+    dest: &Account<TokenAccount>,
+    vesting_account: &VestingAccount,
+    mint_wallet_address: &Pubkey,
+    allowlist: Option<&Account<DestinationAllowlist>>,
+) -> Result<()> {
+    let token_mint = &vesting_account.token_mint;
+    let beneficiary = &vesting_account.beneficiary;
+    require_keys_eq!(dest.mint, *token_mint, VestingError::InvalidMint); // The destination account's mint must match
+
+    let expected_ata = get_associated_token_address(beneficiary, token_mint); // Standard ATA for the beneficiary
+    let admin_ata = get_associated_token_address(mint_wallet_address, token_mint); // ATA of the token minting wallet
+
+    let is_beneficiary_ata = dest.key() == expected_ata && dest.owner == *beneficiary; // Is it the beneficiary's ATA?
+    let is_admin_ata = dest.key() == admin_ata && dest.owner == *mint_wallet_address; // Is it the admin's (mint wallet) ATA?
+
+    let allowed = match vesting_account.destination_policy {
+        DestinationPolicy::BeneficiaryOrMintWallet => is_beneficiary_ata || is_admin_ata,
+        DestinationPolicy::BeneficiaryOnly => is_beneficiary_ata,
+        DestinationPolicy::Allowlist => {
+            is_beneficiary_ata                             // A PlanEditor's list can never lock the beneficiary out
+                || allowlist.is_some_and(|a| a.destinations.contains(&dest.key()))
+        }
+    };
+    require!(allowed, VestingError::DestinationNotAllowed);
+    Ok(())
+}
+
+// Grow a program-owned account to `new_len`, with `payer` covering the extra rent
+fn grow_account<'info>(
+    info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(new_len);
+    let top_up = rent.saturating_sub(info.lamports());
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: info.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+    info.resize(new_len)?;
+    Ok(())
+}
+
+// Resize a program-owned account to `new_len`; growing is paid by `payer`, shrinking refunds the excess rent to it
+fn resize_account<'info>(
+    info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    if new_len >= info.data_len() {
+        return grow_account(info, payer, system_program, new_len);
+    }
+    info.resize(new_len)?;
+    let excess = info.lamports().saturating_sub(Rent::get()?.minimum_balance(new_len));
+    **info.try_borrow_mut_lamports()? -= excess;
+    **payer.try_borrow_mut_lamports()? += excess;
+    Ok(())
+}
+
+// Transfer out of a vault whose authority is the [b"vault_auth", admin, token_vault] PDA
+#[allow(clippy::too_many_arguments)]
+fn transfer_from_vault<'info>(
+    token_program: AccountInfo<'info>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    vault_authority: AccountInfo<'info>,
+    admin: &Pubkey,
+    token_vault: &Pubkey,
+    bump: u8,
+    amount: u64,
+) -> Result<()> {
+    let seeds: &[&[u8]; 4] = &[
+        b"vault_auth",                                     // Authority PDA seeds
+        admin.as_ref(),
+        token_vault.as_ref(),
+        &[bump],
+    ];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program,
+            Transfer {
+                from,
+                to,
+                authority: vault_authority,
+            },
+            &[seeds],                                      // Sign with PDA signer
+        ),
+        amount,
+    )
+}
+
+// Close a vault whose authority is the [b"vault_auth", admin, token_vault] PDA, sending its rent to `destination`
+fn close_vault<'info>(
+    token_program: AccountInfo<'info>,
+    vault: AccountInfo<'info>,
+    destination: AccountInfo<'info>,
+    vault_authority: AccountInfo<'info>,
+    admin: &Pubkey,
+    token_vault: &Pubkey,
+    bump: u8,
+) -> Result<()> {
+    let seeds: &[&[u8]; 4] = &[b"vault_auth", admin.as_ref(), token_vault.as_ref(), &[bump]];
+
+    token::close_account(CpiContext::new_with_signer(
+        token_program,
+        CloseAccount {
+            account: vault,
+            destination,
+            authority: vault_authority,
+        },
+        &[seeds],
+    ))
+}
+
+// Store vesting information
+#[account]
+pub struct VestingAccount {                         // PDA to store vesting metadata
+    pub beneficiary: Pubkey,                        // Beneficiary
+    pub total_amount: u64,                          // Total amount
+    pub released_amount: u64,                       // Cumulative released amount
+    pub start_time: i64,                            // Start time (Unix)
+    pub end_time: i64,                              // End time (Unix)
+    pub last_release_time: i64,                     // Last release time
+    pub token_mint: Pubkey,                         // Token mint
+    pub token_vault: Pubkey,                        // Vault (for this vesting)
+    pub beneficiary_vault: Pubkey,                  // Beneficiary vault (PDA)
+    pub category: String,                           // Category (Team/Marketing, etc.)
+    pub is_active: bool,                            // Is active?
+    pub destination_token_account: Pubkey,          // Final receiving token account (e.g., ATA)
+    pub parent_vault: Pubkey,                       // Parent vault (primary wallet)
+    pub kind: VestingKind,                          // How the releasable amount is computed
+    pub cliff_time: i64,                            // Nothing is releasable before this time (0 = no cliff)
+    pub destination_policy: DestinationPolicy,      // Where released tokens may go
+    pub plan_chunk_count: u16,                      // Plan pages: [b"plans", vesting_account, index] for index in 0..count
+}
+
+#[derive(AnchorDeserialize)]
+struct LegacyVestingAccount {                       // VestingAccount layout before kind/cliff_time
+    beneficiary: Pubkey,
+    total_amount: u64,
+    released_amount: u64,
+    start_time: i64,
+    end_time: i64,
+    last_release_time: i64,
+    token_mint: Pubkey,
+    token_vault: Pubkey,
+    beneficiary_vault: Pubkey,
+    category: String,
+    is_active: bool,
+    destination_token_account: Pubkey,
+    parent_vault: Pubkey,
+}
+
+impl VestingAccount {
+    // Book a payout against total_amount; every release path must go through here
+    pub fn record_release(&mut self, amount: u64, now: i64) -> Result<()> {
+        let released_amount = self
+            .released_amount
+            .checked_add(amount)
+            .ok_or(VestingError::Overflow)?;
+        require!(released_amount <= self.total_amount, VestingError::Overflow); // Never pay out more than the total
+
+        self.released_amount = released_amount;             // Cumulative released amount
+        self.last_release_time = now;                       // Last release time
+        Ok(())
+    }
+
+    // Fails until the cliff has passed
+    pub fn check_cliff(&self, now: i64) -> Result<()> {
+        require!(now >= self.cliff_time, VestingError::CliffNotReached);
+        Ok(())
+    }
+
+    // Amount the clock has unlocked so far for Linear vestings
+    pub fn linear_vested_amount(&self, now: i64) -> Result<u64> {
+        if now <= self.start_time || now < self.cliff_time {
+            return Ok(0);                                   // Not started or still before the cliff
+        }
+        if now >= self.end_time {
+            return Ok(self.total_amount);                   // Fully vested
+        }
+
+        let elapsed = (now - self.start_time) as u128;
+        let duration = (self.end_time - self.start_time) as u128;
+        let vested = (self.total_amount as u128)
+            .checked_mul(elapsed)
+            .ok_or(VestingError::Overflow)?
+            / duration;
+        u64::try_from(vested).map_err(|_| VestingError::Overflow.into())
+    }
+
+    // Unlocked but not yet paid out
+    pub fn releasable_linear_amount(&self, now: i64) -> Result<u64> {
+        Ok(self.linear_vested_amount(now)?.saturating_sub(self.released_amount))
+    }
+
+    // Summary as of `now`; `plans` are the plans of every page (empty for Linear vestings)
+    pub fn info(&self, plans: &[YearlyPlan], now: i64) -> Result<VestingInfo> {
+        let cliff_passed = now >= self.cliff_time;
+        let (releasable_amount, next_release_time) = match self.kind {
+            VestingKind::Plan => {
+                let mut releasable: u64 = 0;
+                let mut next: Option<i64> = None;
+                for plan in plans.iter().filter(|p| !p.released) {
+                    if plan.release_time <= now {
+                        releasable = releasable
+                            .checked_add(plan.remaining_amount())
+                            .ok_or(VestingError::Overflow)?;
+                    } else {
+                        next = Some(next.map_or(plan.release_time, |n| n.min(plan.release_time))); // Earliest future plan
+                    }
+                }
+                if !cliff_passed {
+                    // Everything matured so far unlocks at the cliff
+                    let next = if releasable > 0 {
+                        Some(self.cliff_time)
+                    } else {
+                        next.map(|n| n.max(self.cliff_time))
+                    };
+                    (0, next.unwrap_or(0))
+                } else {
+                    (releasable, next.unwrap_or(0))
+                }
+            }
+            VestingKind::Linear => {
+                let releasable = self.releasable_linear_amount(now)?;
+                let unlock_start = self.start_time.max(self.cliff_time);
+                let next = if self.released_amount >= self.total_amount {
+                    0                                       // Fully paid out
+                } else if now < unlock_start {
+                    unlock_start                            // First unlock
+                } else {
+                    now                                     // Streams continuously
+                };
+                (releasable, next)
+            }
+        };
+
+        Ok(VestingInfo {
+            total_amount: self.total_amount,
+            released_amount: self.released_amount,
+            releasable_amount,
+            next_release_time,                              // 0 = nothing left to release
+            is_active: self.is_active,
+        })
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VestingKind {                              // Release schedule type
+    #[default]
+    Plan,                                           // Discrete YearlyPlan entries in the plan chunk
+    Linear,                                         // Unlocks per second between start_time and end_time
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScheduleValidation {                       // How append_yearly_plan/update_plan_chunk check the schedule
+    #[default]
+    Strict,                                         // Unpaid plans must add up to total_amount - released_amount
+    Incremental,                                    // May still fall short (schedule sent in slices)
+    Skip,                                           // No checks, for importing irregular historical data (not for patches)
+}
+
+impl ScheduleValidation {
+    // Patches always re-check the schedule; Skip is only for importing legacy data
+    pub fn check_patch(self) -> Result<()> {
+        require!(self != ScheduleValidation::Skip, VestingError::ValidationRequired);
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum PlanPatch {                                // Indexed edit applied by patch_plan_chunk
+    SetAmount { index: u16, amount: u64 },          // Change the amount of plan `index`
+    SetReleaseTime { index: u16, release_time: i64 }, // Move plan `index`
+    Insert { index: u16, release_time: i64, amount: u64 }, // New unreleased plan before `index` (len appends)
+    Remove { index: u16 },                          // Drop an unreleased plan
+    Shift { delta: i64 },                           // Move every unreleased plan by `delta` seconds
+}
+
+impl PlanPatch {
+    // Page length after the patch
+    pub fn resized_len(&self, len: usize) -> usize {
+        match self {
+            PlanPatch::Insert { .. } => len + 1,
+            PlanPatch::Remove { .. } => len.saturating_sub(1),
+            _ => len,
+        }
+    }
+
+    // Edit `plans` in place; paid plans are never touched
+    pub fn apply(&self, plans: &mut Vec<YearlyPlan>) -> Result<()> {
+        match *self {
+            PlanPatch::SetAmount { index, amount } => unpaid_plan(plans, index)?.amount = amount,
+            PlanPatch::SetReleaseTime { index, release_time } => {
+                unpaid_plan(plans, index)?.release_time = release_time
+            }
+            PlanPatch::Insert { index, release_time, amount } => {
+                require!(index as usize <= plans.len(), VestingError::PlanIndexOutOfRange);
+                plans.insert(index as usize, YearlyPlan::new(release_time, amount));
+            }
+            PlanPatch::Remove { index } => {
+                unpaid_plan(plans, index)?;
+                plans.remove(index as usize);
+            }
+            PlanPatch::Shift { delta } => {
+                for plan in plans.iter_mut().filter(|p| !p.has_payouts()) {
+                    plan.release_time = plan.release_time.checked_add(delta).ok_or(VestingError::Overflow)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub enum PlanChange {                               // Plan chunk edit staged by queue_plan_change
+    Append { plans: Vec<YearlyPlan>, validation: ScheduleValidation }, // As append_yearly_plan
+    Replace {                                       // As update_plan_chunk
+        plans: Vec<YearlyPlan>,
+        validation: ScheduleValidation,
+        allow_amount_change: bool,
+    },
+    Patch { patch: PlanPatch, validation: ScheduleValidation }, // As patch_plan_chunk
+    PatchZeroCopy { patch: PlanPatch, validation: ScheduleValidation }, // As patch_zero_copy_plans (chunk_index 0)
+}
+
+#[account]
+pub struct PlanTimelock {                           // Notice period for plan chunk changes (PDA)
+    pub delay: i64,                                 // Seconds between queue_plan_change and execute_plan_change
+    pub pending_delay: i64,                         // Shorter delay waiting to take effect
+    pub pending_delay_at: i64,                      // When pending_delay applies (0 = none)
+}
+
+impl PlanTimelock {
+    // Delay in force at `now`
+    pub fn delay_at(&self, now: i64) -> i64 {
+        if self.pending_delay_at != 0 && now >= self.pending_delay_at {
+            self.pending_delay
+        } else {
+            self.delay
+        }
+    }
+}
+
+#[account]
+pub struct PendingPlanChange {                      // Change to one plan chunk page waiting for its delay (PDA)
+    pub vesting_account: Pubkey,
+    pub chunk_index: u16,
+    pub proposer: Pubkey,                           // Paid the rent; refunded on execute/cancel
+    pub change: PlanChange,
+    pub eta: i64,                                   // Earliest execute_plan_change time
+}
+
+impl PendingPlanChange {
+    pub fn space(change: &PlanChange) -> usize {
+        let change_len = borsh::to_vec(change).map_or(0, |v| v.len());
+        DISCRIMINATOR_SIZE + 32 + 2 + 32 + change_len + 8
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ScheduleTemplate {                       // Compact schedule expanded by generate_plan_schedule
+    pub start_time: i64,                            // TGE; periods are counted from here
+    pub tge_bps: u16,                               // Released at start_time, in basis points of the amount
+    pub cliff_periods: u16,                         // Intervals skipped before the first periodic release
+    pub interval: ScheduleInterval,                 // Calendar step between periodic releases
+    pub periods: u16,                               // Number of periodic releases after the cliff
+    pub remainder: RemainderPolicy,                 // Which periodic release absorbs the rounding remainder
+}
+
+impl ScheduleTemplate {
+    // Number of plans the template expands to
+    pub fn plan_count(&self) -> usize {
+        self.periods as usize + usize::from(self.tge_bps > 0)
+    }
+
+    // Expand into plans splitting `amount`; periodic releases fall at start + (cliff_periods + k) intervals, k = 1..=periods
+    pub fn plans(&self, amount: u64) -> Result<Vec<YearlyPlan>> {
+        require!(self.tge_bps <= 10_000, VestingError::InvalidParameters);
+        require!(self.periods > 0 || self.tge_bps == 10_000, VestingError::InvalidParameters); // Something must release the rest
+        require!(self.plan_count() <= MAX_PLANS, VestingError::InsufficientSpace);              // Fits one page
+
+        let tge = (amount as u128 * self.tge_bps as u128 / 10_000) as u64;
+        let rest = amount - tge;
+        let mut plans = Vec::with_capacity(self.plan_count());
+        if self.tge_bps > 0 {
+            plans.push(YearlyPlan::new(self.start_time, tge));
+        }
+        if self.periods == 0 {
+            return Ok(plans);
+        }
+
+        let per_period = rest / self.periods as u64;
+        let remainder = rest % self.periods as u64;
+        for k in 1..=self.periods as u32 {
+            let months = (self.cliff_periods as u32 + k) * self.interval.months();
+            let mut amount = per_period;
+            let takes_remainder = match self.remainder {
+                RemainderPolicy::First => k == 1,
+                RemainderPolicy::Last => k == self.periods as u32,
+            };
+            if takes_remainder {
+                amount += remainder;
+            }
+            plans.push(YearlyPlan::new(add_months(self.start_time, months)?, amount));
+        }
+        Ok(plans)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleInterval {                         // Calendar-month based steps
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl ScheduleInterval {
+    pub fn months(&self) -> u32 {
+        match self {
+            ScheduleInterval::Monthly => 1,
+            ScheduleInterval::Quarterly => 3,
+            ScheduleInterval::Yearly => 12,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RemainderPolicy {                          // Where amount % periods goes
+    First,                                          // First periodic release
+    Last,                                           // Last periodic release
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DestinationPolicy {                        // Allowed destination token accounts
+    #[default]
+    BeneficiaryOrMintWallet,                        // Beneficiary ATA or TokenInfo.mint_wallet_address ATA
+    BeneficiaryOnly,                                // Beneficiary ATA only
+    Allowlist,                                      // Beneficiary ATA or accounts listed in DestinationAllowlist
+}
+
+#[account]
+pub struct DestinationAllowlist {                   // Explicit destinations for DestinationPolicy::Allowlist (PDA)
+    pub vesting_account: Pubkey,                    // Which vesting does it belong to
+    pub destinations: Vec<Pubkey>,                  // Allowed destination token accounts
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct YearlyPlan {                             // Yearly plan unit
+    pub release_time: i64,                          // Release time
+    pub amount: u64,                                // Release amount
+    pub released: bool,                             // Has it been released already?
+    pub released_amount: u64,                       // Paid out so far (partial releases)
+}
+
+impl YearlyPlan {
+    // Unreleased plan paying `amount` at `release_time`
+    pub fn new(release_time: i64, amount: u64) -> Self {
+        YearlyPlan { release_time, amount, released: false, released_amount: 0 }
+    }
+
+    // Has anything been paid out of this plan?
+    pub fn has_payouts(&self) -> bool {
+        self.released || self.released_amount > 0
+    }
+
+    // Amount of this plan not yet paid out
+    pub fn remaining_amount(&self) -> u64 {
+        self.amount.saturating_sub(self.released_amount)
+    }
+
+    // Pay `amount` out of this plan; it is marked released once fully paid
+    pub fn release(&mut self, amount: u64) -> Result<()> {
+        require!(
+            amount > 0 && amount <= self.remaining_amount(),
+            VestingError::InvalidParameters
+        );
+        self.released_amount = self
+            .released_amount
+            .checked_add(amount)
+            .ok_or(VestingError::Overflow)?;
+        self.released = self.released_amount == self.amount;
+        Ok(())
+    }
+
+    // Pay out whatever is left and mark the plan released; returns the amount paid
+    pub fn release_remaining(&mut self) -> u64 {
+        let remaining = self.remaining_amount();
+        self.released_amount = self.amount;
+        self.released = true;
+        remaining
+    }
+
+    // Give `amount` back to this plan (child allocation unwound); it becomes releasable again
+    pub fn restore(&mut self, amount: u64) -> Result<()> {
+        self.amount = self.amount.checked_add(amount).ok_or(VestingError::Overflow)?;
+        self.released = self.remaining_amount() == 0;
+        Ok(())
+    }
+}
+
+#[derive(AnchorDeserialize)]
+struct LegacyYearlyPlan {                           // YearlyPlan layout before partial releases
+    release_time: i64,
+    amount: u64,
+    released: bool,
+}
+
+#[derive(AnchorDeserialize)]
+struct LegacyVestingPlanChunk {                     // VestingPlanChunk holding LegacyYearlyPlan entries
+    vesting_account: Pubkey,
+    plans: Vec<LegacyYearlyPlan>,
+}
+
+#[account]
+pub struct VestingPlanChunk {                       // A bundle of plans (PDA)
+    pub vesting_account: Pubkey,                    // Which vesting does it belong to
+    pub plans: Vec<YearlyPlan>,                     // Array of YearlyPlan
+}
+
+#[account(zero_copy)]
+pub struct ZeroCopyPlanChunk {                      // Fixed-size plan storage read in place (PDA)
+    pub vesting_account: Pubkey,                    // Which vesting does it belong to
+    pub len: u32,                                   // Plans in use at the front of `plans`
+    pub _padding: [u8; 4],
+    pub plans: [ZeroCopyPlan; ZERO_COPY_MAX_PLANS],
+}
+
+impl ZeroCopyPlanChunk {
+    pub fn plans(&self) -> &[ZeroCopyPlan] {
+        &self.plans[..self.len as usize]
+    }
+
+    pub fn plans_mut(&mut self) -> &mut [ZeroCopyPlan] {
+        &mut self.plans[..self.len as usize]
+    }
+
+    pub fn to_plans(&self) -> Vec<YearlyPlan> {
+        self.plans().iter().map(YearlyPlan::from).collect()
+    }
+
+    // Replace the stored plans, clearing unused slots
+    pub fn set_plans(&mut self, plans: &[YearlyPlan]) -> Result<()> {
+        require!(plans.len() <= ZERO_COPY_MAX_PLANS, VestingError::InsufficientSpace);
+        for (slot, plan) in self.plans.iter_mut().zip(plans) {
+            *slot = ZeroCopyPlan::from(plan);
+        }
+        for slot in &mut self.plans[plans.len()..] {
+            *slot = ZeroCopyPlan::default();
+        }
+        self.len = plans.len() as u32;
+        Ok(())
+    }
+}
+
+#[zero_copy]
+#[derive(Default)]
+pub struct ZeroCopyPlan {                           // YearlyPlan without Borsh framing
+    pub release_time: i64,
+    pub amount: u64,
+    pub released_amount: u64,
+    pub released: u8,                               // bool
+    pub _padding: [u8; 7],
+}
+
+impl From<&YearlyPlan> for ZeroCopyPlan {
+    fn from(plan: &YearlyPlan) -> Self {
+        ZeroCopyPlan {
+            release_time: plan.release_time,
+            amount: plan.amount,
+            released_amount: plan.released_amount,
+            released: u8::from(plan.released),
+            _padding: [0; 7],
+        }
+    }
+}
+
+impl From<&ZeroCopyPlan> for YearlyPlan {
+    fn from(plan: &ZeroCopyPlan) -> Self {
+        YearlyPlan {
+            release_time: plan.release_time,
+            amount: plan.amount,
+            released: plan.released != 0,
+            released_amount: plan.released_amount,
+        }
+    }
+}
+
+#[account]
+pub struct PlanAllocation {                         // Parent/child funding ledger of a child vesting (PDA)
+    pub parent_vesting: Pubkey,                     // Vesting whose plans fund the child
+    pub child_vesting: Pubkey,                      // Vesting created by user_create_vesting
+    pub entries: Vec<AllocationEntry>,              // One entry per funded child plan
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AllocationEntry {                        // Parent tranche -> child tranche
+    pub release_time: i64,                          // Shared by the parent and child plan
+    pub amount: u64,                                // Deducted from the parent plan
+    pub parent_plan_chunk: Pubkey,                  // Parent page holding the funding plan
+}
+
+// Account to store admin information
+#[account]
+pub struct AdminConfig {                            // Admin configuration (PDA)
+    pub admin: Pubkey,                              // Admin key
+    pub pending_admin: Pubkey,                      // Proposed by propose_admin (default = none)
+    pub seed_admin: Pubkey,                         // First admin; vault_auth/token_info PDAs are derived from it
+}
+
+#[account]
+pub struct ApprovalConfig {                         // M-of-N signer set for sensitive operations (PDA)
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,                              // Approvals needed
+    pub proposal_count: u64,                        // Id of the next proposal
+}
+
+impl ApprovalConfig {
+    // Approvals that still count; keys removed by update_approval_config no longer do
+    pub fn approval_count(&self, proposal: &Proposal) -> usize {
+        proposal.approvals.iter().filter(|a| self.signers.contains(a)).count()
+    }
+
+    // `proposal` is unused, is for exactly `operation` and is approved by the current threshold
+    pub fn check_approved(&self, proposal: &Proposal, operation: &ProposalOperation) -> Result<()> {
+        require!(!proposal.executed, VestingError::ProposalAlreadyExecuted);
+        require!(proposal.operation == *operation, VestingError::ProposalMismatch);
+        require!(
+            self.approval_count(proposal) >= self.threshold as usize,
+            VestingError::ApprovalRequired
+        );
+        Ok(())
+    }
+}
+
+#[account]
+pub struct Proposal {                               // Staged sensitive operation (PDA)
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub operation: ProposalOperation,               // Must match the executed instruction exactly
+    pub approvals: Vec<Pubkey>,                     // Signers who approved; checked against the current signer set
+    pub executed: bool,                             // A proposal runs once
+}
+
+impl Proposal {
+    pub fn space(operation: &ProposalOperation) -> usize {
+        let operation_len = borsh::to_vec(operation).map_or(0, |v| v.len());
+        DISCRIMINATOR_SIZE + 8 + 32 + operation_len + 4 + MAX_APPROVERS * 32 + 1
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum ProposalOperation {                        // Operations that need M-of-N approval once configured
+    ReplacePlans {                                  // update_plan_chunk
+        vesting_account: Pubkey,
+        chunk_index: u16,
+        plans: Vec<YearlyPlan>,
+        validation: ScheduleValidation,
+        allow_amount_change: bool,
+    },
+    PatchPlans {                                    // patch_plan_chunk
+        vesting_account: Pubkey,
+        chunk_index: u16,
+        patch: PlanPatch,
+        validation: ScheduleValidation,
+    },
+    PatchZeroCopyPlans {                            // patch_zero_copy_plans
+        vesting_account: Pubkey,
+        patch: PlanPatch,
+        validation: ScheduleValidation,
+    },
+    RevokeChildVesting { vesting_account: Pubkey }, // revoke_child_vesting
+    ProposeAdmin { new_admin: Pubkey },             // propose_admin
+    RemoveAdmin,                                    // remove_admin
+    RotateDeployer { new_deployer: Pubkey },        // rotate_deployer
+    Release {                                       // do_vesting to anything but the beneficiary ATA
+        vesting_account: Pubkey,
+        vesting_time: i64,
+        amount: u64,
+        destination: Pubkey,
+    },
+    SetApprovalConfig { signers: Vec<Pubkey>, threshold: u8 }, // update_approval_config
+    SetRoleMembers { role: Role, members: Vec<Pubkey> }, // set_role_members
+    SetPlanTimelock { delay: i64 },                 // set_plan_timelock
+}
+
+#[derive(AnchorDeserialize)]
+struct LegacyAdminConfig {                          // AdminConfig layout before admin handover
+    admin: Pubkey,
+}
+
+impl AdminConfig {
+    // The admin may do everything; other keys only what their role allows
+    pub fn authorizes(&self, roles: Option<&Roles>, role: Role, signer: &Pubkey) -> bool {
+        self.admin == *signer || roles.is_some_and(|r| r.members(role).contains(signer))
+    }
+}
+
+#[account]
+pub struct Roles {                                  // Role members besides the admin (PDA)
+    pub schedulers: Vec<Pubkey>,                    // do_vesting, catch_up_vesting, batch_do_vesting
+    pub plan_editors: Vec<Pubkey>,                  // Create vestings, edit plans and destination policies
+    pub pausers: Vec<Pubkey>,                       // emergency_stop
+    pub closers: Vec<Pubkey>,                       // close_vesting_account, revoke_child_vesting
+    pub token_registrars: Vec<Pubkey>,              // init_token_info, set_tip_pool
+}
+
+impl Roles {
+    pub fn members(&self, role: Role) -> &Vec<Pubkey> {
+        match role {
+            Role::Scheduler => &self.schedulers,
+            Role::PlanEditor => &self.plan_editors,
+            Role::Pauser => &self.pausers,
+            Role::Closer => &self.closers,
+            Role::TokenRegistrar => &self.token_registrars,
+        }
+    }
+
+    pub fn members_mut(&mut self, role: Role) -> &mut Vec<Pubkey> {
+        match role {
+            Role::Scheduler => &mut self.schedulers,
+            Role::PlanEditor => &mut self.plan_editors,
+            Role::Pauser => &mut self.pausers,
+            Role::Closer => &mut self.closers,
+            Role::TokenRegistrar => &mut self.token_registrars,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Scheduler,
+    PlanEditor,
+    Pauser,
+    Closer,
+    TokenRegistrar,
+}
+
+#[account]
+pub struct DeployAdmin {                            // Deployer configuration (PDA)
+    pub deployer: Pubkey,                           // Deployer key
+}
+
+#[derive(Accounts)]
+pub struct InitializeDeployer<'info> {              // initialize_deployer context
+    #[account(mut)]
+    pub deployer: Signer<'info>, // Deployer = signer, must be the program's upgrade authority
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ VestingError::NotDeployAdmin)]
+    pub program: Program<'info, crate::program::Vesting>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(deployer.key()) @ VestingError::NotDeployAdmin)]
+    pub program_data: Account<'info, ProgramData>,  // ProgramData of this program
+
+    #[account(
+        init,
+        payer = deployer,
+        space = 8 + 32, // discriminator + pubkey
+        seeds = [b"deploy_admin"],
+        bump
+    )]
+    pub deploy_admin: Account<'info, DeployAdmin>,  // PDA: ("deploy_admin")
 
     pub system_program: Program<'info, System>,     // System program
 }
 
-// Struct for setting admin during program initialization
 #[derive(Accounts)]
-pub struct Initialize<'info> {                      // initialize context
+pub struct RotateDeployer<'info> {                  // rotate_deployer context
+    pub deployer: Signer<'info>,                    // Current deployer
+
+    #[account(
+        mut,
+        constraint = deploy_admin.deployer == deployer.key() @ VestingError::NotDeployAdmin,
+        seeds = [b"deploy_admin"],
+        bump
+    )]
+    pub deploy_admin: Account<'info, DeployAdmin>,
+
+    /// CHECK: ApprovalConfig if M-of-N approvals are configured, otherwise empty; read by consume_approval
+    #[account(seeds = [b"approvals"], bump)]
+    pub approval_config: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub proposal: Option<Box<Account<'info, Proposal>>>,   // Approved proposal for this exact operation
+}
+
+// Struct for setting admin during program initialization
+#[derive(Accounts)]
+pub struct Initialize<'info> {                      // initialize context
+    #[account(mut)]
+    pub deployer: Signer<'info>,                    // Deployer signer
+
+    #[account(
+        seeds = [b"deploy_admin"],                 // Reuse PDA created above
+        bump
+    )]
+    pub deployer_admin: Account<'info, DeployAdmin>,
+
+    // Scheduler address
+    #[account(mut)]
+    pub admin: Signer<'info>,                       // Admin signer
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = ADMIN_CONFIG_SPACE,
+        seeds = [b"admin"],                         // Create AdminConfig PDA with a fixed seed (kept by remove_admin)
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, vesting_time: i64, params: VestingParams)]
+pub struct DoVesting<'info> {                       // do_vesting context definition
+    // Scheduler admin
+    #[account(mut)]
+    pub admin: Signer<'info>,                       // Calling admin
+
+    #[account(mut)]
+    pub token_vault: Account<'info, TokenAccount>,  // Related vault (used for authority PDA calculation)
+
+    /// CHECK: PDA used as authority for token_vault (seeds: [b"vault_auth", admin.key, token_vault.key])
+    pub vault_authority: UncheckedAccount<'info>,   // PDA itself is verified with seeds/bump
+
+    #[account(
+        mut,
+        constraint = origin_token_account.mint == token_mint.key() @ VestingError::InvalidMint,
+        constraint = origin_token_account.owner == vault_authority.key() @ VestingError::Unauthorized
+    )]
+    pub origin_token_account: Account<'info, TokenAccount>,   // Source token account (verification: PDA calculated directly above)
+
+    #[account(
+        mut,
+        constraint = destination_token_account.mint == token_mint.key() @ VestingError::InvalidMint
+    )]
+    pub destination_token_account: Account<'info, TokenAccount>, // Destination token account (ATA verified)
+
+    #[account(
+        mut,
+        seeds = [b"vesting", beneficiary.key().as_ref(), token_mint.key().as_ref(), &params.vesting_id.to_le_bytes()],
+        bump
+    )]
+    pub vesting_account: Account<'info, VestingAccount>,
+
+    #[account(
+        constraint = admin_config.authorizes(roles.as_deref(), Role::Scheduler, admin.key) @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,            // Admin configuration PDA
+
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
+
+    #[account(mut)]
+    pub plan_chunk: Option<Account<'info, VestingPlanChunk>>, // Page holding the plan (owner checked in the handler); not needed for Linear vestings
+
+    #[account(
+        mut,
+        seeds = [b"zc_plans", vesting_account.key().as_ref()],
+        bump
+    )]
+    pub zero_copy_plans: Option<AccountLoader<'info, ZeroCopyPlanChunk>>, // Used instead of plan_chunk once migrated
+
+    #[account(
+        seeds = [b"destinations", vesting_account.key().as_ref()],
+        bump
+    )]
+    pub destination_allowlist: Option<Box<Account<'info, DestinationAllowlist>>>, // Only needed for DestinationPolicy::Allowlist
+
+    /// CHECK: ApprovalConfig if M-of-N approvals are configured, otherwise empty; read by consume_approval
+    #[account(seeds = [b"approvals"], bump)]
+    pub approval_config: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub proposal: Option<Box<Account<'info, Proposal>>>,   // Approved proposal for this exact operation
+
+    #[account(
+        seeds = [b"token_info", admin_config.seed_admin.as_ref(), token_mint.key().as_ref()], // Registered token information PDA
+        bump
+    )]
+    pub token_info: Box<Account<'info, TokenInfo>>,
+
+    /// CHECK: Beneficiary
+    pub beneficiary: AccountInfo<'info>,                      // Used for key check only
+    pub token_mint: Account<'info, Mint>,                     // Token mint
+
+    pub token_program: Program<'info, Token>,                 // SPL Token Program
+    pub system_program: Program<'info, System>,               // System Program
+}
+
+#[derive(Accounts)]
+#[instruction(params: VestingParams)]
+pub struct CatchUpVesting<'info> {                 // catch_up_vesting context
+    // Scheduler admin
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        constraint = admin_config.authorizes(roles.as_deref(), Role::Scheduler, admin.key) @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
+
+    pub token_vault: Box<Account<'info, TokenAccount>>,       // Related vault (used for authority PDA calculation)
+
+    /// CHECK: PDA used as authority for the beneficiary vault
+    #[account(
+        seeds = [b"vault_auth", admin_config.seed_admin.as_ref(), token_vault.key().as_ref()],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", beneficiary.key().as_ref(), token_mint.key().as_ref(), &params.vesting_id.to_le_bytes()],
+        bump,
+        token::mint = token_mint,
+        token::authority = vault_authority
+    )]
+    pub origin_token_account: Box<Account<'info, TokenAccount>>, // Beneficiary-specific vault
+
+    #[account(mut)]
+    pub destination_token_account: Box<Account<'info, TokenAccount>>, // Verified in check_destination_ata
+
+    #[account(
+        mut,
+        seeds = [b"vesting", beneficiary.key().as_ref(), token_mint.key().as_ref(), &params.vesting_id.to_le_bytes()],
+        bump
+    )]
+    pub vesting_account: Box<Account<'info, VestingAccount>>,
+
+    #[account(
+        seeds = [b"destinations", vesting_account.key().as_ref()],
+        bump
+    )]
+    pub destination_allowlist: Option<Box<Account<'info, DestinationAllowlist>>>, // Only needed for DestinationPolicy::Allowlist
+
+    #[account(
+        seeds = [b"token_info", admin_config.seed_admin.as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub token_info: Box<Account<'info, TokenInfo>>,
+
+    /// CHECK: ApprovalConfig if M-of-N approvals are configured, otherwise empty; read by check_bulk_destination
+    #[account(seeds = [b"approvals"], bump)]
+    pub approval_config: UncheckedAccount<'info>,
+
+    /// CHECK: Beneficiary
+    pub beneficiary: AccountInfo<'info>,                      // Used for key check only
+    pub token_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct BatchDoVesting<'info> {                 // batch_do_vesting context (entries come in remaining_accounts)
+    // Scheduler admin
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        constraint = admin_config.authorizes(roles.as_deref(), Role::Scheduler, admin.key) @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
+
+    pub token_vault: Box<Account<'info, TokenAccount>>,       // Vault shared by every entry
+
+    /// CHECK: PDA used as authority for the beneficiary vaults
+    #[account(
+        seeds = [b"vault_auth", admin_config.seed_admin.as_ref(), token_vault.key().as_ref()],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"token_info", admin_config.seed_admin.as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub token_info: Box<Account<'info, TokenInfo>>,
+
+    /// CHECK: ApprovalConfig if M-of-N approvals are configured, otherwise empty; read by check_bulk_destination
+    #[account(seeds = [b"approvals"], bump)]
+    pub approval_config: UncheckedAccount<'info>,
+
+    pub token_mint: Account<'info, Mint>,                     // Mint shared by every entry
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(vesting_id: u64)]
+pub struct Claim<'info> {                          // claim context
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,                // Beneficiary signer
+
+    #[account(
+        seeds = [b"admin"],                        // Used only to derive the vault authority
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"vesting", beneficiary.key().as_ref(), token_mint.key().as_ref(), &vesting_id.to_le_bytes()],
+        bump,
+        has_one = beneficiary @ VestingError::Unauthorized,
+        has_one = token_mint @ VestingError::InvalidMint,
+        has_one = token_vault @ VestingError::Unauthorized,
+        has_one = beneficiary_vault @ VestingError::Unauthorized
+    )]
+    pub vesting_account: Box<Account<'info, VestingAccount>>,
+
+    #[account(
+        seeds = [b"destinations", vesting_account.key().as_ref()],
+        bump
+    )]
+    pub destination_allowlist: Option<Box<Account<'info, DestinationAllowlist>>>, // Only needed for DestinationPolicy::Allowlist
+
+    #[account(
+        seeds = [b"token_info", admin_config.seed_admin.as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub token_info: Box<Account<'info, TokenInfo>>,           // Registered token information
+
+    pub token_vault: Box<Account<'info, TokenAccount>>,       // Vault the authority PDA was derived from
+
+    /// CHECK: PDA used as authority for beneficiary_vault
+    #[account(
+        seeds = [b"vault_auth", admin_config.seed_admin.as_ref(), token_vault.key().as_ref()],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = vault_authority
+    )]
+    pub beneficiary_vault: Box<Account<'info, TokenAccount>>, // Source: beneficiary-specific vault
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = beneficiary
+    )]
+    pub beneficiary_token_account: Box<Account<'info, TokenAccount>>, // Destination: beneficiary's ATA
+
+    pub token_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CrankRelease<'info> {                   // crank_release context (permissionless)
+    #[account(mut)]
+    pub cranker: Signer<'info>,                    // Anyone; receives the tip
+
+    #[account(
+        seeds = [b"admin"],                        // Used only to derive the vault authority
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(
+        mut,
+        has_one = token_mint @ VestingError::InvalidMint,
+        has_one = token_vault @ VestingError::Unauthorized,
+        has_one = beneficiary_vault @ VestingError::Unauthorized,
+        has_one = destination_token_account @ VestingError::DestinationNotAllowed // Only the recorded destination
+    )]
+    pub vesting_account: Box<Account<'info, VestingAccount>>,
+
+    #[account(
+        seeds = [b"destinations", vesting_account.key().as_ref()],
+        bump
+    )]
+    pub destination_allowlist: Option<Box<Account<'info, DestinationAllowlist>>>, // Only needed for DestinationPolicy::Allowlist
+
+    /// CHECK: ApprovalConfig if M-of-N approvals are configured, otherwise empty; read by check_bulk_destination
+    #[account(seeds = [b"approvals"], bump)]
+    pub approval_config: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"token_info", admin_config.seed_admin.as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub token_info: Box<Account<'info, TokenInfo>>,
+
+    pub token_vault: Box<Account<'info, TokenAccount>>,       // Vault the authority PDA was derived from
+
+    /// CHECK: PDA used as authority for beneficiary_vault
+    #[account(
+        seeds = [b"vault_auth", admin_config.seed_admin.as_ref(), token_vault.key().as_ref()],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = vault_authority
+    )]
+    pub beneficiary_vault: Box<Account<'info, TokenAccount>>, // Source: beneficiary-specific vault
+
+    #[account(mut)]
+    pub destination_token_account: Box<Account<'info, TokenAccount>>, // Recorded destination
+
+    #[account(
+        mut,
+        seeds = [b"tip_pool", token_mint.key().as_ref()],
+        bump
+    )]
+    pub tip_pool: Option<Account<'info, TipPool>>,            // Optional lamport tip for the caller
+
+    pub token_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetRoleMembers<'info> {                // set_role_members context
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = ROLES_SPACE,
+        seeds = [b"roles"],
+        bump
+    )]
+    pub roles: Account<'info, Roles>,
+
+    /// CHECK: ApprovalConfig if M-of-N approvals are configured, otherwise empty; read by consume_approval
+    #[account(seeds = [b"approvals"], bump)]
+    pub approval_config: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub proposal: Option<Box<Account<'info, Proposal>>>,   // Approved proposal for this exact operation
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetTipPool<'info> {                     // set_tip_pool context
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        constraint = admin_config.authorizes(roles.as_deref(), Role::TokenRegistrar, admin.key) @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = TIP_POOL_SPACE,
+        seeds = [b"tip_pool", token_mint.key().as_ref()],
+        bump
+    )]
+    pub tip_pool: Account<'info, TipPool>,
+
+    pub token_mint: Account<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LockupVault<'info> {                    // lockup_vault context
+    #[account(mut)]
+    // Token issuer address
+    pub admin: Signer<'info>,                      // Admin signer
+
+    /// CHECK: Scheduler address
+    #[account(mut)]
+    pub scheduler_admin: AccountInfo<'info>,       // Scheduler (can be matched with AdminConfig.admin)
+
+    #[account(mut)]
+    pub admin_token_account: Account<'info, TokenAccount>, // Admin's token account
+    pub token_mint: Account<'info, Mint>,                  // Mint
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        token::mint = token_mint,
+        token::authority = vault_authority,        // Assign vault_authority PDA as the new authority
+        seeds = [b"vault", admin.key().as_ref(), token_mint.key().as_ref()], // Vault PDA based on admin/mint
+        bump
+    )]
+    pub token_vault: Account<'info, TokenAccount>, // Vault (admin+mint)
+
+    /// CHECK: PDA used as the new authority for token_vault
+    #[account(
+        seeds = [b"vault_auth", scheduler_admin.key().as_ref(), token_vault.key().as_ref()], // scheduler+vault
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,  // Vault authority PDA
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+// Create vesting
+#[derive(Accounts)]
+#[instruction(params: VestingParams)]
+pub struct CreateVesting<'info> {                 // create_vesting context
+    // Scheduler address
+    #[account(mut)]
+    pub admin: Signer<'info>,                     // Admin signer
+
+    #[account(
+        constraint = admin_config.authorizes(roles.as_deref(), Role::PlanEditor, admin.key) @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
+
+    #[account(
+        seeds = [b"token_info", admin_config.seed_admin.as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub token_info: Box<Account<'info, TokenInfo>>, // Registered token information
+
+    /// CHECK: Beneficiary
+    pub beneficiary: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = VESTING_ACCOUNT_SPACE,
+        seeds = [b"vesting", beneficiary.key().as_ref(), token_mint.key().as_ref(), &params.vesting_id.to_le_bytes()], // beneficiary+mint+id
+        bump
+    )]
+    pub vesting_account: Account<'info, VestingAccount>,      // New vesting account
+
+    pub token_mint: Account<'info, Mint>,
+
+    // Token minting wallet address + mint address
+    #[account(mut)]
+    pub token_vault: Box<Account<'info, TokenAccount>>,       // The vault this vesting references
+
+    // Primary wallet vault to transfer tokens to the secondary wallet
+    #[account(mut)]
+    pub parent_vault: Box<Account<'info, TokenAccount>>,      // Parent vault (main)
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        token::mint = token_mint,
+        token::authority = vault_authority,
+        seeds = [b"vault", beneficiary.key().as_ref(), token_mint.key().as_ref(), &params.vesting_id.to_le_bytes()], // Beneficiary-specific Vault
+        bump
+    )]
+    pub beneficiary_vault: Account<'info, TokenAccount>,      // User-specific Vault
+
+    /// CHECK: PDA used as the new authority for token_vault
+    #[account(
+        seeds = [b"vault_auth", admin_config.seed_admin.as_ref(), token_vault.key().as_ref()], // admin+token_vault 
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,             // Authority PDA (for transfer signature)
+    // For primary wallet -> main vault -> on transfer, send from main vault to beneficiary_vault (primary wallet)
+    #[account(mut)]
+    pub beneficiary_token_account: Account<'info, TokenAccount>, // Beneficiary's final receiving ATA, etc.
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: VestingParams)]
+pub struct UserCreateVesting<'info> {             // user_create_vesting context
+    // Scheduler address
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        constraint = admin_config.authorizes(roles.as_deref(), Role::PlanEditor, admin.key) @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
+
+    #[account(
+        seeds = [b"token_info", admin_config.seed_admin.as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub token_info: Box<Account<'info, TokenInfo>>, // Token information
+
+    /// CHECK: Beneficiary
+    pub beneficiary: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = VESTING_ACCOUNT_SPACE,
+        seeds = [b"vesting", beneficiary.key().as_ref(), token_mint.key().as_ref(), &params.vesting_id.to_le_bytes()],
+        bump
+    )]
+    pub vesting_account: Box<Account<'info, VestingAccount>>, // New vesting
+
+    pub token_mint: Account<'info, Mint>,
+
+    // Token minting wallet address + mint address
+    #[account(mut)]
+    pub token_vault: Box<Account<'info, TokenAccount>>,       // Referenced vault
+
+    // Primary wallet vault to transfer tokens to the secondary wallet
+    #[account(mut)]
+    pub parent_vault: Box<Account<'info, TokenAccount>>,      // Parent vault
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        token::mint = token_mint,
+        token::authority = vault_authority,
+        seeds = [b"vault", beneficiary.key().as_ref(), token_mint.key().as_ref(), &params.vesting_id.to_le_bytes()],
+        bump
+    )]
+    pub beneficiary_vault: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA used as the new authority for token_vault
+    #[account(
+        seeds = [b"vault_auth", admin_config.seed_admin.as_ref(), token_vault.key().as_ref()],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+    // For primary wallet -> main vault -> on transfer, send from main vault to beneficiary_vault (primary wallet)
+    #[account(mut)]
+    pub beneficiary_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = parent_vesting_account.beneficiary_vault == parent_vault.key() @ VestingError::InvalidParameters, // Funded from the parent's own vault
+        constraint = parent_vesting_account.token_mint == token_mint.key() @ VestingError::InvalidMint
+    )]
+    pub parent_vesting_account: Box<Account<'info, VestingAccount>>,   // Parent vesting
+
+    /// CHECK: a page of the parent vesting, or its zero-copy chunk once migrated; checked by read_parent_plans
+    pub parent_plan_chunk: UncheckedAccount<'info>,                    // Parent plans
+
+    #[account(
+        init,
+        payer = admin,
+        space = plan_allocation_space(0),
+        seeds = [b"allocation", vesting_account.key().as_ref()],
+        bump
+    )]
+    pub allocation: Box<Account<'info, PlanAllocation>>,              // Which parent tranche funds which child tranche
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(chunk_index: u16, plans: Vec<YearlyPlan>)]
+pub struct AppendYearlyPlan<'info> {               // append_yearly_plan context
+    #[account(mut)]
+    pub vesting_account: Account<'info, VestingAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = plan_chunk.data_len().max(plan_chunk_space(0)), // Starts empty and grows on every append; existing pages keep their size
+        seeds = [b"plans", vesting_account.key().as_ref(), &chunk_index.to_le_bytes()],
+        bump
+    )]
+    pub plan_chunk: Account<'info, VestingPlanChunk>,         // Create/update the target plan chunk
+
+    /// CHECK: must not exist; vestings on zero-copy storage take no plan chunk pages
+    #[account(
+        seeds = [b"zc_plans", vesting_account.key().as_ref()],
+        bump,
+        constraint = zero_copy_plans.data_is_empty() @ VestingError::InvalidParameters
+    )]
+    pub zero_copy_plans: UncheckedAccount<'info>,
+
+    /// CHECK: a page of the parent vesting, or its zero-copy chunk once migrated; checked by read_parent_plans
+    #[account(mut)]
+    pub parent_plan_chunk: Option<UncheckedAccount<'info>>,  // Parent plan (optional)
+
+    #[account(
+        constraint = allocation.as_ref().is_some_and(|a| a.parent_vesting == parent_vesting_account.key()) @ VestingError::PlanChunkMismatch
+    )]
+    pub parent_vesting_account: Option<Box<Account<'info, VestingAccount>>>, // Required for child vestings; its timelock applies too
+
+    #[account(
+        mut,
+        seeds = [b"allocation", vesting_account.key().as_ref()],
+        bump
+    )]
+    pub allocation: Option<Account<'info, PlanAllocation>>,   // Required for child vestings
+
+    #[account(mut)]
+    pub admin: Signer<'info>,                                  // Admin
+
+    #[account(
+        constraint = admin_config.authorizes(roles.as_deref(), Role::PlanEditor, admin.key) @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
+
+    /// CHECK: PlanTimelock if plan changes are timelocked, otherwise empty; read by check_plan_timelock
+    #[account(seeds = [b"plan_timelock"], bump)]
+    pub plan_timelock: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chunk_index: u16, patch: PlanPatch)]
+pub struct PatchPlanChunk<'info> {                // patch_plan_chunk context
+    pub vesting_account: Account<'info, VestingAccount>, // Owner of the plan chunk
+
+    #[account(
+        mut,
+        seeds = [b"plans", vesting_account.key().as_ref(), &chunk_index.to_le_bytes()], // Only this vesting's page
+        bump,
+        realloc = plan_chunk_space(patch.resized_len(plan_chunk.plans.len())), // Insert grows, remove shrinks and refunds
+        realloc::payer = admin,
+        realloc::zero = false
+    )]
+    pub plan_chunk: Account<'info, VestingPlanChunk>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        constraint = admin_config.authorizes(roles.as_deref(), Role::PlanEditor, admin.key) @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
+
+    /// CHECK: PlanTimelock if plan changes are timelocked, otherwise empty; read by check_plan_timelock
+    #[account(seeds = [b"plan_timelock"], bump)]
+    pub plan_timelock: UncheckedAccount<'info>,
+
+    /// CHECK: ApprovalConfig if M-of-N approvals are configured, otherwise empty; read by consume_approval
+    #[account(seeds = [b"approvals"], bump)]
+    pub approval_config: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub proposal: Option<Box<Account<'info, Proposal>>>,   // Approved proposal for this exact operation
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPlanTimelock<'info> {               // set_plan_timelock context
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = PLAN_TIMELOCK_SPACE,
+        seeds = [b"plan_timelock"],
+        bump
+    )]
+    pub plan_timelock: Account<'info, PlanTimelock>,
+
+    /// CHECK: ApprovalConfig if M-of-N approvals are configured, otherwise empty; read by consume_approval
+    #[account(seeds = [b"approvals"], bump)]
+    pub approval_config: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub proposal: Option<Box<Account<'info, Proposal>>>,   // Approved proposal for this exact operation
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chunk_index: u16, change: PlanChange)]
+pub struct QueuePlanChange<'info> {               // queue_plan_change context
+    pub vesting_account: Account<'info, VestingAccount>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = PendingPlanChange::space(&change),
+        seeds = [b"pending_plans", vesting_account.key().as_ref(), &chunk_index.to_le_bytes()], // One staged change per page
+        bump
+    )]
+    pub pending_change: Account<'info, PendingPlanChange>,
+
+    #[account(seeds = [b"plan_timelock"], bump)]
+    pub plan_timelock: Account<'info, PlanTimelock>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        constraint = admin_config.authorizes(roles.as_deref(), Role::PlanEditor, admin.key) @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chunk_index: u16)]
+pub struct CancelPlanChange<'info> {              // cancel_plan_change context
+    pub vesting_account: Account<'info, VestingAccount>,
+
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer @ VestingError::InvalidParameters,
+        seeds = [b"pending_plans", vesting_account.key().as_ref(), &chunk_index.to_le_bytes()],
+        bump
+    )]
+    pub pending_change: Account<'info, PendingPlanChange>,
+
+    #[account(mut)]
+    pub proposer: SystemAccount<'info>,           // Gets the rent back
+
+    pub admin: Signer<'info>,
+
+    #[account(
+        constraint = admin_config.authorizes(roles.as_deref(), Role::PlanEditor, admin.key) @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
+}
+
+#[derive(Accounts)]
+#[instruction(chunk_index: u16)]
+pub struct ExecutePlanChange<'info> {             // execute_plan_change context
+    #[account(mut)]
+    pub vesting_account: Box<Account<'info, VestingAccount>>,
+
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer @ VestingError::InvalidParameters,
+        seeds = [b"pending_plans", vesting_account.key().as_ref(), &chunk_index.to_le_bytes()],
+        bump
+    )]
+    pub pending_change: Box<Account<'info, PendingPlanChange>>,
+
+    #[account(mut)]
+    pub proposer: SystemAccount<'info>,           // Gets the rent back
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = plan_chunk.data_len().max(plan_chunk_space(0)), // Only a staged append may open a new page; existing pages keep their size
+        seeds = [b"plans", vesting_account.key().as_ref(), &chunk_index.to_le_bytes()],
+        bump
+    )]
+    pub plan_chunk: Box<Account<'info, VestingPlanChunk>>,
+
+    /// CHECK: must not exist; vestings on zero-copy storage take no plan chunk pages
+    #[account(
+        seeds = [b"zc_plans", vesting_account.key().as_ref()],
+        bump,
+        constraint = zero_copy_plans.data_is_empty() @ VestingError::InvalidParameters
+    )]
+    pub zero_copy_plans: UncheckedAccount<'info>,
+
+    /// CHECK: a page of the parent vesting, or its zero-copy chunk once migrated; checked by read_parent_plans
+    #[account(mut)]
+    pub parent_plan_chunk: Option<UncheckedAccount<'info>>,  // Staged appends to child vestings
+
+    #[account(
+        mut,
+        seeds = [b"allocation", vesting_account.key().as_ref()],
+        bump
+    )]
+    pub allocation: Option<Box<Account<'info, PlanAllocation>>>,   // Required for child vestings
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        constraint = admin_config.authorizes(roles.as_deref(), Role::PlanEditor, admin.key) @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
+
+    /// CHECK: ApprovalConfig if M-of-N approvals are configured, otherwise empty; read by consume_approval
+    #[account(seeds = [b"approvals"], bump)]
+    pub approval_config: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub proposal: Option<Box<Account<'info, Proposal>>>,   // Approved proposal for a staged replacement
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteZeroCopyPlanChange<'info> {     // execute_zero_copy_plan_change context
+    pub vesting_account: Account<'info, VestingAccount>,
+
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer @ VestingError::InvalidParameters,
+        seeds = [b"pending_plans", vesting_account.key().as_ref(), &0u16.to_le_bytes()],
+        bump
+    )]
+    pub pending_change: Box<Account<'info, PendingPlanChange>>,
+
+    #[account(mut)]
+    pub proposer: SystemAccount<'info>,           // Gets the rent back
+
+    #[account(
+        mut,
+        seeds = [b"zc_plans", vesting_account.key().as_ref()],
+        bump
+    )]
+    pub zero_copy_plans: AccountLoader<'info, ZeroCopyPlanChunk>,
+
+    pub admin: Signer<'info>,
+
+    #[account(
+        constraint = admin_config.authorizes(roles.as_deref(), Role::PlanEditor, admin.key) @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
+
+    /// CHECK: ApprovalConfig if M-of-N approvals are configured, otherwise empty; read by consume_approval
+    #[account(seeds = [b"approvals"], bump)]
+    pub approval_config: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub proposal: Option<Box<Account<'info, Proposal>>>,   // Approved proposal for the staged patch
+}
+
+#[derive(Accounts)]
+#[instruction(template: ScheduleTemplate)]
+pub struct GeneratePlanSchedule<'info> {           // generate_plan_schedule context
+    #[account(mut)]
+    pub vesting_account: Account<'info, VestingAccount>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = plan_chunk_space(template.plan_count()),
+        seeds = [b"plans", vesting_account.key().as_ref(), &0u16.to_le_bytes()], // First page
+        bump
+    )]
+    pub plan_chunk: Account<'info, VestingPlanChunk>,
+
+    /// CHECK: must not exist; vestings on zero-copy storage take no plan chunk pages
+    #[account(
+        seeds = [b"zc_plans", vesting_account.key().as_ref()],
+        bump,
+        constraint = zero_copy_plans.data_is_empty() @ VestingError::InvalidParameters
+    )]
+    pub zero_copy_plans: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        constraint = admin_config.authorizes(roles.as_deref(), Role::PlanEditor, admin.key) @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
+
+    /// CHECK: PlanTimelock if plan changes are timelocked, otherwise empty; read by check_plan_timelock
+    #[account(seeds = [b"plan_timelock"], bump)]
+    pub plan_timelock: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chunk_index: u16, plans: Vec<YearlyPlan>)]
+pub struct UpdatePlanChunk<'info> {               // update_plan_chunk context
+    pub vesting_account: Account<'info, VestingAccount>, // Owner of the plan chunk
+
+    #[account(
+        mut,
+        seeds = [b"plans", vesting_account.key().as_ref(), &chunk_index.to_le_bytes()], // Only this vesting's page
+        bump,
+        realloc = plan_chunk_space(plans.len()),   // Grow or shrink to the new plans; shrinking refunds rent to admin
+        realloc::payer = admin,
+        realloc::zero = false
+    )]
+    pub plan_chunk: Account<'info, VestingPlanChunk>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        constraint = admin_config.authorizes(roles.as_deref(), Role::PlanEditor, admin.key) @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
+
+    /// CHECK: PlanTimelock if plan changes are timelocked, otherwise empty; read by check_plan_timelock
+    #[account(seeds = [b"plan_timelock"], bump)]
+    pub plan_timelock: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,   // Needed when the chunk grows
+
+    /// CHECK: ApprovalConfig if M-of-N approvals are configured, otherwise empty; read by consume_approval
+    #[account(seeds = [b"approvals"], bump)]
+    pub approval_config: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub proposal: Option<Box<Account<'info, Proposal>>>,   // Approved proposal for this exact operation
+}
+
+#[derive(Accounts)]
+pub struct SetDestinationPolicy<'info> {          // set_destination_policy context
     #[account(mut)]
-    pub deployer: Signer<'info>,                    // Deployer signer
+    pub admin: Signer<'info>,
 
     #[account(
-        seeds = [b"deploy_admin"],                 // Reuse PDA created above
+        constraint = admin_config.authorizes(roles.as_deref(), Role::PlanEditor, admin.key) @ VestingError::Unauthorized,
+        seeds = [b"admin"],
         bump
     )]
-    pub deployer_admin: Account<'info, DeployAdmin>,
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
 
-    // Scheduler address
     #[account(mut)]
-    pub admin: Signer<'info>,                       // Admin signer
+    pub vesting_account: Box<Account<'info, VestingAccount>>,
 
     #[account(
-        init,
+        init_if_needed,
         payer = admin,
-        space = 8 + 32, // discriminator + pubkey
-        seeds = [b"admin"],                         // Create AdminConfig PDA with a fixed seed
+        space = DESTINATION_ALLOWLIST_SPACE,
+        seeds = [b"destinations", vesting_account.key().as_ref()],
         bump
     )]
-    pub admin_config: Account<'info, AdminConfig>,
+    pub destination_allowlist: Box<Account<'info, DestinationAllowlist>>,
 
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(amount: u64, vesting_time: i64, params: VestingParams)]
-pub struct DoVesting<'info> {                       // do_vesting context definition
-    // Scheduler admin
-    #[account(mut)]
-    pub admin: Signer<'info>,                       // Calling admin
-
+pub struct MigrateVestingAccount<'info> {         // migrate_vesting_account context
     #[account(mut)]
-    pub token_vault: Account<'info, TokenAccount>,  // Related vault (used for authority PDA calculation)
-
-    /// CHECK: PDA used as authority for token_vault (seeds: [b"vault_auth", admin.key, token_vault.key])
-    pub vault_authority: UncheckedAccount<'info>,   // PDA itself is verified with seeds/bump
+    pub admin: Signer<'info>,                     // Pays the extra rent
 
     #[account(
-        mut,
-        constraint = origin_token_account.mint == token_mint.key() @ VestingError::InvalidMint,
-        constraint = origin_token_account.owner == vault_authority.key() @ VestingError::Unauthorized
+        has_one = admin @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
     )]
-    pub origin_token_account: Account<'info, TokenAccount>,   // Source token account (verification: PDA calculated directly above)
+    pub admin_config: Account<'info, AdminConfig>,
 
-    #[account(
-        mut,
-        constraint = destination_token_account.mint == token_mint.key() @ VestingError::InvalidMint
-    )]
-    pub destination_token_account: Account<'info, TokenAccount>, // Destination token account (ATA verified)
+    /// CHECK: legacy layout cannot be deserialized as VestingAccount; decoded in the handler
+    #[account(mut, owner = crate::ID @ VestingError::Unauthorized)]
+    pub vesting_account: UncheckedAccount<'info>,
 
-    #[account(
-        mut,
-        seeds = [b"vesting", beneficiary.key().as_ref(), token_mint.key().as_ref(), &params.vesting_id.to_le_bytes()],
-        bump
-    )]
-    pub vesting_account: Account<'info, VestingAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(entries: Vec<AllocationEntry>)]
+pub struct MigratePlanAllocation<'info> {         // migrate_plan_allocation context
+    #[account(mut)]
+    pub admin: Signer<'info>,                     // Pays for the ledger
 
     #[account(
-        has_one = admin @ VestingError::Unauthorized,         // The admin field must match this admin
+        constraint = admin_config.authorizes(roles.as_deref(), Role::PlanEditor, admin.key) @ VestingError::Unauthorized,
         seeds = [b"admin"],
         bump
     )]
-    pub admin_config: Account<'info, AdminConfig>,            // Admin configuration PDA
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
 
     #[account(
-        mut,
-        seeds = [b"plans", vesting_account.key().as_ref()],   // Plan chunk PDA for this vesting
-        bump
+        constraint = vesting_account.token_vault != vesting_account.parent_vault @ VestingError::InvalidParameters, // Child vestings only
+        constraint = vesting_account.parent_vault == parent_vesting_account.beneficiary_vault @ VestingError::InvalidParameters, // Funded by this parent
+        constraint = vesting_account.token_mint == parent_vesting_account.token_mint @ VestingError::InvalidMint
     )]
-    pub plan_chunk: Account<'info, VestingPlanChunk>,
+    pub vesting_account: Box<Account<'info, VestingAccount>>, // Child vesting
+
+    pub parent_vesting_account: Box<Account<'info, VestingAccount>>,
 
     #[account(
-        seeds = [b"token_info", admin.key().as_ref(), token_mint.key().as_ref()], // Registered token information PDA
+        init,
+        payer = admin,
+        space = plan_allocation_space(entries.len()),
+        seeds = [b"allocation", vesting_account.key().as_ref()],
         bump
     )]
-    pub token_info: Box<Account<'info, TokenInfo>>,
-
-    /// CHECK: Beneficiary
-    pub beneficiary: AccountInfo<'info>,                      // Used for key check only
-    pub token_mint: Account<'info, Mint>,                     // Token mint
+    pub allocation: Box<Account<'info, PlanAllocation>>,
 
-    pub token_program: Program<'info, Token>,                 // SPL Token Program
-    pub system_program: Program<'info, System>,               // System Program
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct LockupVault<'info> {                    // lockup_vault context
+pub struct MigrateToZeroCopyPlans<'info> {        // migrate_to_zero_copy_plans context
     #[account(mut)]
-    // Token issuer address
-    pub admin: Signer<'info>,                      // Admin signer
+    pub admin: Signer<'info>,                     // Pays for the zero-copy chunk, receives the page rent
 
-    /// CHECK: Scheduler address
-    #[account(mut)]
-    pub scheduler_admin: AccountInfo<'info>,       // Scheduler (can be matched with AdminConfig.admin)
+    #[account(
+        has_one = admin @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
 
     #[account(mut)]
-    pub admin_token_account: Account<'info, TokenAccount>, // Admin's token account
-    pub token_mint: Account<'info, Mint>,                  // Mint
+    pub vesting_account: Account<'info, VestingAccount>,
 
     #[account(
-        init_if_needed,
+        init,
         payer = admin,
-        token::mint = token_mint,
-        token::authority = vault_authority,        // Assign vault_authority PDA as the new authority
-        seeds = [b"vault", admin.key().as_ref(), token_mint.key().as_ref()], // Vault PDA based on admin/mint
+        space = ZERO_COPY_PLAN_CHUNK_SPACE,
+        seeds = [b"zc_plans", vesting_account.key().as_ref()],
         bump
     )]
-    pub token_vault: Account<'info, TokenAccount>, // Vault (admin+mint)
+    pub zero_copy_plans: AccountLoader<'info, ZeroCopyPlanChunk>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PatchZeroCopyPlans<'info> {            // patch_zero_copy_plans context
+    pub admin: Signer<'info>,
 
-    /// CHECK: PDA used as the new authority for token_vault
     #[account(
-        seeds = [b"vault_auth", scheduler_admin.key().as_ref(), token_vault.key().as_ref()], // scheduler+vault
+        constraint = admin_config.authorizes(roles.as_deref(), Role::PlanEditor, admin.key) @ VestingError::Unauthorized,
+        seeds = [b"admin"],
         bump
     )]
-    pub vault_authority: UncheckedAccount<'info>,  // Vault authority PDA
+    pub admin_config: Account<'info, AdminConfig>,
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
+
+    /// CHECK: PlanTimelock if plan changes are timelocked, otherwise empty; read by check_plan_timelock
+    #[account(seeds = [b"plan_timelock"], bump)]
+    pub plan_timelock: UncheckedAccount<'info>,
+
+    pub vesting_account: Account<'info, VestingAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"zc_plans", vesting_account.key().as_ref()],
+        bump
+    )]
+    pub zero_copy_plans: AccountLoader<'info, ZeroCopyPlanChunk>,
+
+    /// CHECK: ApprovalConfig if M-of-N approvals are configured, otherwise empty; read by consume_approval
+    #[account(seeds = [b"approvals"], bump)]
+    pub approval_config: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub proposal: Option<Box<Account<'info, Proposal>>>,   // Approved proposal for this exact operation
 }
 
-// Create vesting
 #[derive(Accounts)]
-#[instruction(params: VestingParams)]
-pub struct CreateVesting<'info> {                 // create_vesting context
-    // Scheduler address
+pub struct MigratePlanChunk<'info> {              // migrate_plan_chunk context
     #[account(mut)]
-    pub admin: Signer<'info>,                     // Admin signer
+    pub admin: Signer<'info>,                     // Pays for page 0, receives the legacy rent
 
     #[account(
-        has_one = admin @ VestingError::Unauthorized, // Must match AdminConfig.admin
+        has_one = admin @ VestingError::Unauthorized,
         seeds = [b"admin"],
         bump
     )]
     pub admin_config: Account<'info, AdminConfig>,
 
     #[account(
-        seeds = [b"token_info", admin.key().as_ref(), token_mint.key().as_ref()],
-        bump
+        mut,
+        constraint = vesting_account.plan_chunk_count == 0 @ VestingError::PlanChunkMismatch // Run migrate_vesting_account first
     )]
-    pub token_info: Box<Account<'info, TokenInfo>>, // Registered token information
+    pub vesting_account: Box<Account<'info, VestingAccount>>,
 
-    /// CHECK: Beneficiary
-    pub beneficiary: AccountInfo<'info>,
+    /// CHECK: legacy layout cannot be deserialized as VestingPlanChunk; decoded in the handler
+    #[account(
+        mut,
+        owner = crate::ID @ VestingError::Unauthorized,
+        seeds = [b"plans", vesting_account.key().as_ref()], // Legacy (unpaged) address
+        bump
+    )]
+    pub legacy_plan_chunk: UncheckedAccount<'info>,
 
     #[account(
         init,
         payer = admin,
-        space = VESTING_ACCOUNT_SPACE,
-        seeds = [b"vesting", beneficiary.key().as_ref(), token_mint.key().as_ref(), &params.vesting_id.to_le_bytes()], // beneficiary+mint+id
+        space = plan_chunk_space(0),               // Grown to the legacy plan count in the handler
+        seeds = [b"plans", vesting_account.key().as_ref(), &0u16.to_le_bytes()],
         bump
     )]
-    pub vesting_account: Account<'info, VestingAccount>,      // New vesting account
+    pub plan_chunk: Account<'info, VestingPlanChunk>,
 
-    pub token_mint: Account<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
 
-    // Token minting wallet address + mint address
-    #[account(mut)]
-    pub token_vault: Box<Account<'info, TokenAccount>>,       // The vault this vesting references
+#[derive(Accounts)]
+pub struct GetVestingInfo<'info> {                // get_vesting_info context (read-only)
+    pub vesting_account: Box<Account<'info, VestingAccount>>,
+}
 
-    // Primary wallet vault to transfer tokens to the secondary wallet
+#[derive(Accounts)]
+pub struct GetPlanBalance<'info> {                // get_plan_balance context (read-only)
+    pub vesting_account: Box<Account<'info, VestingAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyStop<'info> {                 // emergency_stop context
     #[account(mut)]
-    pub parent_vault: Box<Account<'info, TokenAccount>>,      // Parent vault (main)
+    pub admin: Signer<'info>,
 
     #[account(
-        init_if_needed,
-        payer = admin,
-        token::mint = token_mint,
-        token::authority = vault_authority,
-        seeds = [b"vault", beneficiary.key().as_ref(), token_mint.key().as_ref(), &params.vesting_id.to_le_bytes()], // Beneficiary-specific Vault
+        constraint = admin_config.authorizes(roles.as_deref(), Role::Pauser, admin.key) @ VestingError::Unauthorized,
+        seeds = [b"admin"],
         bump
     )]
-    pub beneficiary_vault: Account<'info, TokenAccount>,      // User-specific Vault
+    pub admin_config: Account<'info, AdminConfig>,
+
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
+
+    /// CHECK: beneficiary account
+    pub beneficiary: AccountInfo<'info>,          // Key check only
+
+    pub token_mint: Account<'info, Mint>,
 
-    /// CHECK: PDA used as the new authority for token_vault
     #[account(
-        seeds = [b"vault_auth", admin.key().as_ref(), token_vault.key().as_ref()], // admin+token_vault 
-        bump
+        mut,
+        constraint = vesting_account.beneficiary == beneficiary.key() @ VestingError::Unauthorized, // Beneficiary must match
+        constraint = vesting_account.token_mint == token_mint.key() @ VestingError::Unauthorized   // Token must match
     )]
-    pub vault_authority: UncheckedAccount<'info>,             // Authority PDA (for transfer signature)
-    // For primary wallet -> main vault -> on transfer, send from main vault to beneficiary_vault (primary wallet)
-    #[account(mut)]
-    pub beneficiary_token_account: Account<'info, TokenAccount>, // Beneficiary's final receiving ATA, etc.
+    pub vesting_account: Account<'info, VestingAccount>,
+}
 
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub rent: Sysvar<'info, Rent>,
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct VestingParams {                        // Parameters for creating/releasing vesting
+    pub vesting_id: u64,                          // Identifier (PDA seed)
+    pub total_amount: u64,                        // Total amount
+    pub released_amount: u64,                     // Already released amount (initial transfer allowed)
+    pub start_time: i64,                          // Start time
+    pub end_time: i64,                            // End time
+    pub category: String,                         // Category
+    pub kind: VestingKind,                        // Plan-based or linear
+    pub cliff_time: i64,                          // Cliff (0 = no cliff)
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct BatchReleaseResult {                   // Per-entry outcome returned by batch_do_vesting
+    pub vesting_account: Pubkey,
+    pub amount: u64,                              // Released amount (0 when skipped)
+    pub error_code: u32,                          // 0 on success, otherwise the error that skipped the entry
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct PlanBalance {                          // Returned by get_plan_balance
+    pub amount: u64,
+    pub released_amount: u64,
+    pub remaining_amount: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct VestingInfo {                          // (For querying) Vesting summary info struct
+    pub total_amount: u64,
+    pub released_amount: u64,
+    pub releasable_amount: u64,
+    pub next_release_time: i64,
+    pub is_active: bool,
 }
 
+// Return PDA rent
 #[derive(Accounts)]
-#[instruction(params: VestingParams)]
-pub struct UserCreateVesting<'info> {             // user_create_vesting context
-    // Scheduler address
+pub struct CloseVestingAccount<'info> {           // close_vesting_account context
+    // Scheduler admin
     #[account(mut)]
     pub admin: Signer<'info>,
 
     #[account(
-        has_one = admin @ VestingError::Unauthorized, // Matches AdminConfig.admin
+        constraint = admin_config.authorizes(roles.as_deref(), Role::Closer, admin.key) @ VestingError::Unauthorized,
         seeds = [b"admin"],
         bump
     )]
     pub admin_config: Account<'info, AdminConfig>,
 
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
+
+    #[account(mut, close = admin)]
+    pub vesting_account: Account<'info, VestingAccount>,      // On close, return rent to admin
+
     #[account(
-        seeds = [b"token_info", admin.key().as_ref(), token_mint.key().as_ref()],
-        bump
+        constraint = beneficiary_vault.key() == vesting_account.beneficiary_vault @ VestingError::Unauthorized,
+        constraint = beneficiary_vault.amount == 0 @ VestingError::VaultNotEmpty, // Close only if vault is empty
     )]
-    pub token_info: Box<Account<'info, TokenInfo>>, // Token information
+    pub beneficiary_vault: Account<'info, TokenAccount>,
 
-    /// CHECK: Beneficiary
-    pub beneficiary: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeChildVesting<'info> {             // revoke_child_vesting context
+    #[account(mut)]
+    pub admin: Signer<'info>,
 
     #[account(
-        init,
-        payer = admin,
-        space = VESTING_ACCOUNT_SPACE,
-        seeds = [b"vesting", beneficiary.key().as_ref(), token_mint.key().as_ref(), &params.vesting_id.to_le_bytes()],
+        constraint = admin_config.authorizes(roles.as_deref(), Role::Closer, admin.key) @ VestingError::Unauthorized,
+        seeds = [b"admin"],
         bump
     )]
-    pub vesting_account: Box<Account<'info, VestingAccount>>, // New vesting
-
-    pub token_mint: Account<'info, Mint>,
+    pub admin_config: Account<'info, AdminConfig>,
 
-    // Token minting wallet address + mint address
-    #[account(mut)]
-    pub token_vault: Box<Account<'info, TokenAccount>>,       // Referenced vault
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,                 // Members allowed besides the admin
 
-    // Primary wallet vault to transfer tokens to the secondary wallet
-    #[account(mut)]
-    pub parent_vault: Box<Account<'info, TokenAccount>>,      // Parent vault
+    #[account(
+        mut,
+        close = admin,
+        has_one = token_vault @ VestingError::Unauthorized,
+        has_one = beneficiary_vault @ VestingError::Unauthorized,
+        has_one = parent_vault @ VestingError::Unauthorized
+    )]
+    pub vesting_account: Account<'info, VestingAccount>,      // Child vesting; rent returns to admin
 
     #[account(
-        init_if_needed,
-        payer = admin,
-        token::mint = token_mint,
-        token::authority = vault_authority,
-        seeds = [b"vault", beneficiary.key().as_ref(), token_mint.key().as_ref(), &params.vesting_id.to_le_bytes()],
+        mut,
+        close = admin,
+        seeds = [b"allocation", vesting_account.key().as_ref()],
         bump
     )]
-    pub beneficiary_vault: Account<'info, TokenAccount>,
+    pub allocation: Account<'info, PlanAllocation>,          // Funding ledger of the child
 
-    /// CHECK: PDA used as the new authority for token_vault
+    pub token_vault: Account<'info, TokenAccount>,            // Used for authority PDA calculation
+
+    /// CHECK: PDA owning beneficiary_vault
     #[account(
-        seeds = [b"vault_auth", admin.key().as_ref(), token_vault.key().as_ref()],
+        seeds = [b"vault_auth", admin_config.seed_admin.as_ref(), token_vault.key().as_ref()],
         bump
     )]
     pub vault_authority: UncheckedAccount<'info>,
-    // For primary wallet -> main vault -> on transfer, send from main vault to beneficiary_vault (primary wallet)
+
     #[account(mut)]
-    pub beneficiary_token_account: Account<'info, TokenAccount>,
+    pub beneficiary_vault: Account<'info, TokenAccount>,      // Child vault; drained to parent_vault and closed
 
     #[account(mut)]
-    pub parent_vesting_account: Box<Account<'info, VestingAccount>>,   // Parent vesting
+    pub parent_vault: Account<'info, TokenAccount>,           // Receives the unreleased tokens
 
-    #[account(
-        mut,
-        seeds = [b"plans", parent_vesting_account.key().as_ref()],     // Parent plan chunk
-        bump
-    )]
-    pub parent_plan_chunk: Box<Account<'info, VestingPlanChunk>>,      // Parent plans
+    /// CHECK: ApprovalConfig if M-of-N approvals are configured, otherwise empty; read by consume_approval
+    #[account(seeds = [b"approvals"], bump)]
+    pub approval_config: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub proposal: Option<Box<Account<'info, Proposal>>>,   // Approved proposal for this exact operation
 
-    pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
-    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct AppendYearlyPlan<'info> {               // append_yearly_plan context
-    #[account(mut)]
-    pub vesting_account: Account<'info, VestingAccount>,
+pub struct ProposeAdmin<'info> {                  // propose_admin context
+    pub proposer: Signer<'info>,                  // Deployer or current admin
 
     #[account(
-        init_if_needed,
-        payer = admin,
-        space = 8 + 32 + 4 + (52 * (8 + 8 + 1)),   // Assuming approx. 52 plans (size calculation in comments)
-        seeds = [b"plans", vesting_account.key().as_ref()],
+        seeds = [b"deploy_admin"],
         bump
     )]
-    pub plan_chunk: Account<'info, VestingPlanChunk>,         // Create/update the target plan chunk
-
-    #[account(mut)]
-    pub parent_plan_chunk: Option<Account<'info, VestingPlanChunk>>, // Parent plan (optional)
-
-    #[account(mut)]
-    pub admin: Signer<'info>,                                  // Admin
+    pub deployer_admin: Account<'info, DeployAdmin>,
 
     #[account(
-        has_one = admin @ VestingError::Unauthorized, 
+        mut,
         seeds = [b"admin"],
         bump
     )]
     pub admin_config: Account<'info, AdminConfig>,
 
-    pub system_program: Program<'info, System>,
-}
+    /// CHECK: ApprovalConfig if M-of-N approvals are configured, otherwise empty; read by consume_approval
+    #[account(seeds = [b"approvals"], bump)]
+    pub approval_config: UncheckedAccount<'info>,
 
-#[derive(Accounts)]
-pub struct UpdatePlanChunk<'info> {               // update_plan_chunk context
     #[account(mut)]
-    pub plan_chunk: Account<'info, VestingPlanChunk>,
+    pub proposal: Option<Box<Account<'info, Proposal>>>,   // Approved proposal for this exact operation
+}
 
-    #[account(mut)]
-    pub admin: Signer<'info>,
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {                   // accept_admin context
+    pub new_admin: Signer<'info>,
 
     #[account(
-        has_one = admin @ VestingError::Unauthorized, 
+        mut,
+        constraint = admin_config.pending_admin == new_admin.key() @ VestingError::Unauthorized, // Never matches Pubkey::default()
         seeds = [b"admin"],
         bump
     )]
@@ -866,7 +4456,7 @@ pub struct UpdatePlanChunk<'info> {               // update_plan_chunk context
 }
 
 #[derive(Accounts)]
-pub struct EmergencyStop<'info> {                 // emergency_stop context
+pub struct InitApprovalConfig<'info> {            // init_approval_config context
     #[account(mut)]
     pub admin: Signer<'info>,
 
@@ -877,68 +4467,108 @@ pub struct EmergencyStop<'info> {                 // emergency_stop context
     )]
     pub admin_config: Account<'info, AdminConfig>,
 
-    /// CHECK: beneficiary account
-    pub beneficiary: AccountInfo<'info>,          // Key check only
+    #[account(
+        init,
+        payer = admin,
+        space = APPROVAL_CONFIG_SPACE,
+        seeds = [b"approvals"],
+        bump
+    )]
+    pub approval_config: Account<'info, ApprovalConfig>,
 
-    pub token_mint: Account<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateApprovalConfig<'info> {          // update_approval_config context
+    pub signer: Signer<'info>,                    // Anyone may submit once the proposal is approved
 
     #[account(
         mut,
-        constraint = vesting_account.beneficiary == beneficiary.key() @ VestingError::Unauthorized, // Beneficiary must match
-        constraint = vesting_account.token_mint == token_mint.key() @ VestingError::Unauthorized   // Token must match
+        seeds = [b"approvals"],
+        bump
     )]
-    pub vesting_account: Account<'info, VestingAccount>,
+    pub approval_config: Account<'info, ApprovalConfig>,
+
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize)]
-pub struct VestingParams {                        // Parameters for creating/releasing vesting
-    pub vesting_id: u64,                          // Identifier (PDA seed)
-    pub total_amount: u64,                        // Total amount
-    pub released_amount: u64,                     // Already released amount (initial transfer allowed)
-    pub start_time: i64,                          // Start time
-    pub end_time: i64,                            // End time
-    pub category: String,                         // Category
+#[derive(Accounts)]
+#[instruction(operation: ProposalOperation)]
+pub struct CreateProposal<'info> {                // create_proposal context
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = approval_config.signers.contains(&proposer.key()) @ VestingError::Unauthorized,
+        seeds = [b"approvals"],
+        bump
+    )]
+    pub approval_config: Account<'info, ApprovalConfig>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = Proposal::space(&operation),
+        seeds = [b"proposal", approval_config.proposal_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    pub system_program: Program<'info, System>,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize)]
-pub struct VestingInfo {                          // (For querying) Vesting summary info struct
-    pub total_amount: u64,
-    pub released_amount: u64,
-    pub releasable_amount: u64,
-    pub next_release_time: i64,
-    pub is_active: bool,
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {               // approve_proposal context
+    pub approver: Signer<'info>,
+
+    #[account(seeds = [b"approvals"], bump)]
+    pub approval_config: Account<'info, ApprovalConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
 }
 
-// Return PDA rent
 #[derive(Accounts)]
-pub struct CloseVestingAccount<'info> {           // close_vesting_account context
-    // Scheduler admin
+pub struct CloseProposal<'info> {                 // close_proposal context
     #[account(mut)]
-    pub admin: Signer<'info>,
+    pub proposer: Signer<'info>,
 
     #[account(
-        has_one = admin,
-        seeds = [b"admin"],
+        mut,
+        close = proposer,
+        has_one = proposer @ VestingError::Unauthorized,
+        seeds = [b"proposal", proposal.id.to_le_bytes().as_ref()],
         bump
     )]
-    pub admin_config: Account<'info, AdminConfig>,
+    pub proposal: Account<'info, Proposal>,
+}
 
-    #[account(mut, close = admin)]
-    pub vesting_account: Account<'info, VestingAccount>,      // On close, return rent to admin
+#[derive(Accounts)]
+pub struct MigrateAdminConfig<'info> {            // migrate_admin_config context
+    #[account(mut)]
+    pub deployer: Signer<'info>,                  // Pays the extra rent
 
     #[account(
-        mut,
-        close = admin,
-        seeds = [b"plans", vesting_account.key().as_ref()],  // Close plan chunk as well
+        seeds = [b"deploy_admin"],
         bump
     )]
-    pub plan_chunk: Account<'info, VestingPlanChunk>,
+    pub deployer_admin: Account<'info, DeployAdmin>,
 
+    /// CHECK: legacy layout cannot be deserialized as AdminConfig; decoded in the handler
     #[account(
-        constraint = beneficiary_vault.key() == vesting_account.beneficiary_vault @ VestingError::Unauthorized,
-        constraint = beneficiary_vault.amount == 0 @ VestingError::VaultNotEmpty, // Close only if vault is empty
+        mut,
+        owner = crate::ID @ VestingError::Unauthorized,
+        seeds = [b"admin"],
+        bump
     )]
-    pub beneficiary_vault: Account<'info, TokenAccount>,
+    pub admin_config: UncheckedAccount<'info>,
 
     pub system_program: Program<'info, System>,
 }
@@ -960,11 +4590,108 @@ pub struct RemoveAdmin<'info> {                   // remove_admin context
 
     #[account(
         mut,
-        close = deployer,
         seeds = [b"admin"],
         bump
     )]
-    pub admin_config: Account<'info, AdminConfig>,           // Close AdminConfig and return rent to deployer
+    pub admin_config: Account<'info, AdminConfig>,           // Admin cleared, seed_admin kept
+
+    /// CHECK: ApprovalConfig if M-of-N approvals are configured, otherwise empty; read by consume_approval
+    #[account(seeds = [b"approvals"], bump)]
+    pub approval_config: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub proposal: Option<Box<Account<'info, Proposal>>>,   // Approved proposal for this exact operation
+}
+
+#[account]
+pub struct TipPool {                              // Per-mint lamport pool for crank_release tips (PDA)
+    pub token_mint: Pubkey,
+    pub tip_lamports: u64,                        // Paid per successful crank
+}
+
+#[event]
+pub struct PlanTimelockSet {                      // Emitted by set_plan_timelock
+    pub delay: i64,
+    pub effective_at: i64,                        // Later than now when the delay was shortened
+}
+
+#[event]
+pub struct PlanChangeQueued {                     // Emitted by queue_plan_change
+    pub pending_change: Pubkey,
+    pub vesting_account: Pubkey,
+    pub chunk_index: u16,
+    pub proposer: Pubkey,
+    pub change: PlanChange,                       // What will be applied
+    pub eta: i64,                                 // Earliest execution time
+}
+
+#[event]
+pub struct PlanChangeCancelled {                  // Emitted by cancel_plan_change
+    pub pending_change: Pubkey,
+    pub vesting_account: Pubkey,
+    pub chunk_index: u16,
+    pub cancelled_by: Pubkey,
+}
+
+#[event]
+pub struct PlanChangeExecuted {                   // Emitted by execute_plan_change
+    pub pending_change: Pubkey,
+    pub vesting_account: Pubkey,
+    pub chunk_index: u16,
+}
+
+#[event]
+pub struct ProposalCreated {                      // Emitted by create_proposal
+    pub proposal: Pubkey,
+    pub id: u64,
+    pub proposer: Pubkey,
+}
+
+#[event]
+pub struct ProposalApproved {                     // Emitted by approve_proposal
+    pub proposal: Pubkey,
+    pub approver: Pubkey,
+    pub approvals: u8,                            // Approvals so far
+    pub threshold: u8,
+}
+
+#[event]
+pub struct ProposalExecuted {                     // Emitted when an approved proposal is consumed
+    pub proposal: Pubkey,
+    pub id: u64,
+}
+
+#[event]
+pub struct DeployerRotated {                      // Emitted by rotate_deployer
+    pub previous_deployer: Pubkey,
+    pub new_deployer: Pubkey,
+}
+
+#[event]
+pub struct AdminProposed {                        // Emitted by propose_admin
+    pub admin: Pubkey,                            // Current admin
+    pub pending_admin: Pubkey,                    // Proposed admin (default = proposal withdrawn)
+    pub proposer: Pubkey,
+}
+
+#[event]
+pub struct AdminChanged {                         // Emitted by accept_admin
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
+#[event]
+pub struct ChildVestingRevoked {                  // Emitted by revoke_child_vesting
+    pub child_vesting: Pubkey,
+    pub parent_vesting: Pubkey,
+    pub returned_amount: u64,                     // Moved back to parent_vault
+}
+
+#[event]
+pub struct PlansReleased {                        // Emitted when several plans are settled at once
+    pub vesting_account: Pubkey,
+    pub amount: u64,                              // Total transferred
+    pub release_times: Vec<i64>,                  // release_time of every plan settled
 }
 
 #[account]
@@ -994,17 +4721,21 @@ pub struct InitTokenInfo<'info> {                 // init_token_info context
         init,
         payer = scheduler_admin,
         space = 8 + 4 + 32 + 4 + 10 + 8 + 32 + 32, // Approximate space calculation (including String length prefix)
-        seeds = [b"token_info", scheduler_admin.key().as_ref(), token_mint.key().as_ref()],
+        seeds = [b"token_info", admin_config.seed_admin.as_ref(), token_mint.key().as_ref()], // Registered under the first admin
         bump
     )]
     pub token_info: Account<'info, TokenInfo>,    // New TokenInfo PDA
 
     #[account(
+        constraint = admin_config.authorizes(roles.as_deref(), Role::TokenRegistrar, scheduler_admin.key) @ VestingError::Unauthorized,
         seeds = [b"admin"],                      // Existing AdminConfig (read-only)
         bump
     )]
     pub admin_config: Account<'info, AdminConfig>,
 
+    #[account(seeds = [b"roles"], bump)]
+    pub roles: Option<Account<'info, Roles>>,     // Members allowed besides the admin
+
     pub token_mint: Account<'info, Mint>,         // Mint
 
     pub system_program: Program<'info, System>,
@@ -1038,4 +4769,762 @@ pub enum VestingError {                           // Custom error definitions
     VaultNotEmpty,                                // Vault must have a zero balance
     #[msg("Invalid Mint")]
     InvalidMint,                                  // Mint mismatch
+    #[msg("Cliff has not been reached yet")]
+    CliffNotReached,                              // Before cliff_time
+    #[msg("Destination token account is not allowed by the vesting's destination policy")]
+    DestinationNotAllowed,                        // Destination policy violation
+    #[msg("Plan chunk is full")]
+    InsufficientSpace,                            // No room left in the plan chunk
+    #[msg("Every plan chunk of the vesting must be passed exactly once")]
+    PlanChunkMismatch,                            // Missing, duplicate or foreign plan chunk page
+    #[msg("Plans must be sorted by release_time")]
+    PlansNotSorted,                               // Out-of-order release_time
+    #[msg("Two plans share the same release_time")]
+    DuplicatePlanTime,                            // Duplicate release_time
+    #[msg("Plan release_time is outside start_time..=end_time")]
+    PlanOutsideVestingPeriod,                     // Plan outside the vesting period
+    #[msg("Plan amount must be greater than zero")]
+    ZeroPlanAmount,                               // Empty plan
+    #[msg("Plans do not add up to the unreleased vesting amount")]
+    ScheduleAmountMismatch,                       // Sum of plans != total_amount - released_amount
+    #[msg("Plans that have paid out cannot be changed or removed")]
+    ReleasedPlanModified,                         // Edit touches a (partially) released plan
+    #[msg("Replacement changes the unreleased amount of the plan chunk")]
+    UnreleasedAmountChanged,                      // Needs allow_amount_change
+    #[msg("Plan index is out of range")]
+    PlanIndexOutOfRange,                          // No plan at that index
+    #[msg("Plan patches cannot skip schedule validation")]
+    ValidationRequired,                           // ScheduleValidation::Skip on a patch
+    #[msg("Operation needs a proposal approved by the configured threshold")]
+    ApprovalRequired,                             // Missing proposal or not enough approvals
+    #[msg("Proposal does not match this operation")]
+    ProposalMismatch,                             // Different operation or arguments
+    #[msg("Proposal has already been executed")]
+    ProposalAlreadyExecuted,                      // Proposals run once
+    #[msg("Signer has already approved this proposal")]
+    AlreadyApproved,                              // Duplicate approval
+    #[msg("Plan changes are timelocked; use queue_plan_change")]
+    TimelockRequired,                             // Direct edit of a started vesting's plans
+    #[msg("Timelock delay has not passed yet")]
+    TimelockNotExpired,                           // execute_plan_change before eta
+    #[msg("Child vesting has no allocation ledger")]
+    AllocationNotFound,                           // Missing PlanAllocation
+    #[msg("No unreleased parent plan with the same release_time")]
+    UnmatchedAllocation,                          // Child plan cannot be funded
+    #[msg("Token account is frozen")]
+    TokenAccountFrozen,                           // Batch entry whose transfer would fail
+    #[msg("Child vesting plans follow their allocation ledger and cannot be edited")]
+    AllocatedPlansLocked,                         // Replace/patch of a child vesting's plans
+    #[msg("An admin is already set; remove it first")]
+    AdminAlreadySet,                              // initialize while an admin is in place
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 86_400;
+
+    fn at(year: i64, month: i64, day: i64) -> i64 {
+        days_from_civil(year, month, day) * DAY
+    }
+
+    fn vesting(total_amount: u64, released_amount: u64) -> VestingAccount {
+        VestingAccount {
+            beneficiary: Pubkey::new_unique(),
+            total_amount,
+            released_amount,
+            start_time: 1_000,
+            end_time: 2_000,
+            last_release_time: 0,
+            token_mint: Pubkey::new_unique(),
+            token_vault: Pubkey::new_unique(),
+            beneficiary_vault: Pubkey::new_unique(),
+            category: String::from("Team"),
+            is_active: true,
+            destination_token_account: Pubkey::new_unique(),
+            parent_vault: Pubkey::default(),
+            kind: VestingKind::Plan,
+            cliff_time: 0,
+            destination_policy: DestinationPolicy::BeneficiaryOrMintWallet,
+            plan_chunk_count: 1,
+        }
+    }
+
+    fn error_is<T>(result: Result<T>, expected: VestingError) -> bool {
+        matches!(result, Err(e) if e == expected.into())
+    }
+
+    fn paid(release_time: i64, amount: u64, released_amount: u64) -> YearlyPlan {
+        YearlyPlan { released: released_amount == amount, released_amount, ..YearlyPlan::new(release_time, amount) }
+    }
+
+    fn token_account_data(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Vec<u8> {
+        use anchor_lang::solana_program::program_pack::Pack;
+        use anchor_spl::token::spl_token::state::{Account as SplAccount, AccountState};
+        let mut data = vec![0; SplAccount::LEN];
+        SplAccount { mint: *mint, owner: *owner, amount, state: AccountState::Initialized, ..Default::default() }
+            .pack_into_slice(&mut data);
+        data
+    }
+
+    fn account_data<T: AccountSerialize>(account: &T) -> Vec<u8> {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    // Accounts that outlive the test, for helpers that borrow `&'info [AccountInfo<'info>]`
+    fn leaked_account(key: Pubkey, data: Vec<u8>, writable: bool) -> AccountInfo<'static> {
+        AccountInfo::new(
+            Box::leak(Box::new(key)),
+            false,
+            writable,
+            Box::leak(Box::new(1u64)),
+            Box::leak(data.into_boxed_slice()),
+            &crate::ID,
+            false,
+            0,
+        )
+    }
+
+    fn leaked_infos(infos: Vec<AccountInfo<'static>>) -> &'static [AccountInfo<'static>] {
+        Box::leak(infos.into_boxed_slice())
+    }
+
+    fn template(tge_bps: u16, periods: u16, remainder: RemainderPolicy) -> ScheduleTemplate {
+        ScheduleTemplate {
+            start_time: at(2024, 1, 31),
+            tge_bps,
+            cliff_periods: 0,
+            interval: ScheduleInterval::Monthly,
+            periods,
+            remainder,
+        }
+    }
+
+    #[test]
+    fn civil_days_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2024, 2, 29), 19_782);
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));     // Before the epoch
+        assert_eq!(days_from_civil(1900, 3, 1), -25_508);
+        for days in (-1_000_000..1_000_000).step_by(997) {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn add_months_clamps_to_month_end() {
+        assert_eq!(add_months(at(2023, 1, 31), 1).unwrap(), at(2023, 2, 28));
+        assert_eq!(add_months(at(2024, 1, 31), 1).unwrap(), at(2024, 2, 29)); // Leap year
+        assert_eq!(add_months(at(2100, 1, 31), 1).unwrap(), at(2100, 2, 28)); // Century, not a leap year
+        assert_eq!(add_months(at(2000, 1, 31), 1).unwrap(), at(2000, 2, 29)); // 400-year leap year
+        assert_eq!(add_months(at(2024, 3, 31), 1).unwrap(), at(2024, 4, 30));
+        assert_eq!(add_months(at(2024, 2, 29), 12).unwrap(), at(2025, 2, 28));
+        assert_eq!(add_months(at(2024, 11, 15), 3).unwrap(), at(2025, 2, 15)); // Crosses the year
+        assert_eq!(add_months(at(2024, 5, 10) + 3_600, 0).unwrap(), at(2024, 5, 10) + 3_600);
+    }
+
+    #[test]
+    fn add_months_keeps_time_of_day_before_epoch() {
+        assert_eq!(add_months(-1, 2).unwrap(), at(1970, 2, 28) + DAY - 1);
+        assert_eq!(add_months(at(1960, 1, 31) + 60, 1).unwrap(), at(1960, 2, 29) + 60);
+    }
+
+    #[test]
+    fn template_remainder_first_and_last() {
+        let first = template(0, 3, RemainderPolicy::First).plans(1_000).unwrap();
+        let amounts: Vec<u64> = first.iter().map(|p| p.amount).collect();
+        assert_eq!(amounts, vec![334, 333, 333]);
+        let times: Vec<i64> = first.iter().map(|p| p.release_time).collect();
+        assert_eq!(times, vec![at(2024, 2, 29), at(2024, 3, 31), at(2024, 4, 30)]);
+
+        let last = template(0, 3, RemainderPolicy::Last).plans(1_000).unwrap();
+        let amounts: Vec<u64> = last.iter().map(|p| p.amount).collect();
+        assert_eq!(amounts, vec![333, 333, 334]);
+    }
+
+    #[test]
+    fn template_tge_bounds() {
+        let none = template(0, 4, RemainderPolicy::Last);
+        assert_eq!(none.plan_count(), 4);
+        assert!(none.plans(1_000).unwrap().iter().all(|p| p.release_time > none.start_time));
+
+        let split = template(2_500, 3, RemainderPolicy::Last).plans(1_000).unwrap();
+        assert!(split[0] == YearlyPlan::new(at(2024, 1, 31), 250));
+        assert_eq!(split.iter().map(|p| p.amount).sum::<u64>(), 1_000);
+
+        let all = template(10_000, 0, RemainderPolicy::First).plans(1_000).unwrap();
+        assert!(all == vec![YearlyPlan::new(at(2024, 1, 31), 1_000)]);
+
+        assert!(template(10_001, 3, RemainderPolicy::First).plans(1_000).is_err());
+        assert!(template(5_000, 0, RemainderPolicy::First).plans(1_000).is_err()); // Nothing releases the rest
+    }
+
+    #[test]
+    fn template_cliff_and_interval() {
+        let mut t = template(0, 2, RemainderPolicy::Last);
+        t.cliff_periods = 1;
+        t.interval = ScheduleInterval::Quarterly;
+        let times: Vec<i64> = t.plans(10).unwrap().iter().map(|p| p.release_time).collect();
+        assert_eq!(times, vec![at(2024, 7, 31), at(2024, 10, 31)]);
+    }
+
+    #[test]
+    fn linear_vesting_follows_the_clock() {
+        let mut v = vesting(1_000, 0);
+        v.kind = VestingKind::Linear;
+        assert_eq!(v.linear_vested_amount(500).unwrap(), 0);     // Before start
+        assert_eq!(v.linear_vested_amount(1_000).unwrap(), 0);   // At start
+        assert_eq!(v.linear_vested_amount(1_001).unwrap(), 1);
+        assert_eq!(v.linear_vested_amount(1_500).unwrap(), 500);
+        assert_eq!(v.linear_vested_amount(2_000).unwrap(), 1_000); // At the end
+        assert_eq!(v.linear_vested_amount(5_000).unwrap(), 1_000);
+
+        v.released_amount = 300;
+        assert_eq!(v.releasable_linear_amount(1_500).unwrap(), 200);
+        assert_eq!(v.releasable_linear_amount(1_200).unwrap(), 0); // Never negative
+    }
+
+    #[test]
+    fn cliff_holds_back_everything_until_it_passes() {
+        let mut v = vesting(1_000, 0);
+        v.kind = VestingKind::Linear;
+        v.cliff_time = 1_250;
+        assert_eq!(v.linear_vested_amount(1_249).unwrap(), 0);   // Before the cliff
+        assert_eq!(v.linear_vested_amount(1_250).unwrap(), 250); // At the cliff: everything accrued so far
+        assert_eq!(v.linear_vested_amount(2_000).unwrap(), 1_000);
+        assert!(error_is(v.check_cliff(1_249), VestingError::CliffNotReached));
+        assert!(v.check_cliff(1_250).is_ok());
+    }
+
+    #[test]
+    fn check_schedule_accepts_valid_schedules() {
+        let v = vesting(300, 0);
+        let plans = [YearlyPlan::new(1_000, 100), YearlyPlan::new(1_500, 100), YearlyPlan::new(2_000, 100)];
+        assert!(check_schedule(&v, &plans, &[], &[], ScheduleValidation::Strict).is_ok());
+        assert!(check_schedule(&v, &plans[..2], &[], &[], ScheduleValidation::Incremental).is_ok());
+        assert!(check_schedule(&v, &plans[1..], &plans[..1], &[], ScheduleValidation::Strict).is_ok()); // Other pages count
+        assert!(check_schedule(&v, &plans[..1], &[], &plans[1..], ScheduleValidation::Strict).is_ok());
+        let unsorted = [YearlyPlan::new(1_500, 100), YearlyPlan::new(1_000, 100)];
+        assert!(check_schedule(&v, &unsorted, &[], &[], ScheduleValidation::Skip).is_ok());
+    }
+
+    #[test]
+    fn check_schedule_rejects_bad_plans() {
+        let v = vesting(300, 0);
+        let strict = ScheduleValidation::Strict;
+        let unsorted = [YearlyPlan::new(1_500, 150), YearlyPlan::new(1_000, 150)];
+        assert!(error_is(check_schedule(&v, &unsorted, &[], &[], strict), VestingError::PlansNotSorted));
+        let duplicate = [YearlyPlan::new(1_500, 150), YearlyPlan::new(1_500, 150)];
+        assert!(error_is(check_schedule(&v, &duplicate, &[], &[], strict), VestingError::DuplicatePlanTime));
+        let page = [YearlyPlan::new(1_500, 150)];
+        assert!(error_is(check_schedule(&v, &page, &page, &[], strict), VestingError::DuplicatePlanTime));
+        let early = [YearlyPlan::new(999, 300)];
+        assert!(error_is(check_schedule(&v, &early, &[], &[], strict), VestingError::PlanOutsideVestingPeriod));
+        let late = [YearlyPlan::new(2_001, 300)];
+        assert!(error_is(check_schedule(&v, &late, &[], &[], strict), VestingError::PlanOutsideVestingPeriod));
+        let zero = [YearlyPlan::new(1_500, 0), YearlyPlan::new(2_000, 300)];
+        assert!(error_is(check_schedule(&v, &zero, &[], &[], strict), VestingError::ZeroPlanAmount));
+    }
+
+    #[test]
+    fn check_schedule_orders_pages() {
+        let v = vesting(300, 0);
+        let strict = ScheduleValidation::Strict;
+        let first = [YearlyPlan::new(1_000, 100), YearlyPlan::new(1_200, 100)];
+        let second = [YearlyPlan::new(1_100, 100)];                  // Falls between the plans of page 0
+        assert!(error_is(check_schedule(&v, &second, &first, &[], strict), VestingError::PlansNotSorted));
+        assert!(error_is(check_schedule(&v, &first, &[], &second, strict), VestingError::PlansNotSorted));
+        let middle = [YearlyPlan::new(1_500, 100)];
+        let last = [YearlyPlan::new(1_400, 100)];
+        assert!(error_is(
+            check_schedule(&v, &middle, &first[..1], &last, strict),
+            VestingError::PlansNotSorted
+        ));
+        assert!(error_is(
+            check_schedule(&v, &[], &middle, &last, ScheduleValidation::Incremental), // Emptied page
+            VestingError::PlansNotSorted
+        ));
+    }
+
+    #[test]
+    fn check_schedule_sums_unpaid_amounts() {
+        let v = vesting(300, 100);
+        let plans = [paid(1_000, 100, 100), YearlyPlan::new(1_500, 100), YearlyPlan::new(2_000, 100)];
+        assert!(check_schedule(&v, &plans, &[], &[], ScheduleValidation::Strict).is_ok());
+        assert!(error_is(
+            check_schedule(&v, &plans[..2], &[], &[], ScheduleValidation::Strict),
+            VestingError::ScheduleAmountMismatch
+        ));
+        let over = [YearlyPlan::new(1_500, 150), YearlyPlan::new(2_000, 100)];
+        assert!(error_is(
+            check_schedule(&v, &over, &[], &[], ScheduleValidation::Incremental),
+            VestingError::ScheduleAmountMismatch
+        ));
+    }
+
+    #[test]
+    fn check_paid_plans_kept_rejects_edits() {
+        let old = vec![paid(1_000, 100, 40), YearlyPlan::new(2_000, 100)];
+        assert!(check_paid_plans_kept(&old, &old).is_ok());
+        assert!(error_is(check_paid_plans_kept(&old, &old[1..]), VestingError::ReleasedPlanModified)); // Removed
+        let edited = vec![paid(1_000, 90, 40), YearlyPlan::new(2_000, 110)];
+        assert!(error_is(check_paid_plans_kept(&old, &edited), VestingError::ReleasedPlanModified));
+        let made_up = vec![paid(1_000, 100, 40), paid(2_000, 100, 100)];
+        assert!(error_is(check_paid_plans_kept(&old, &made_up), VestingError::ReleasedPlanModified));
+    }
+
+    #[test]
+    fn patch_leaves_paid_plans_alone() {
+        let original = vec![paid(1_000, 100, 100), paid(1_500, 100, 40), YearlyPlan::new(2_000, 100)];
+
+        for patch in [
+            PlanPatch::SetAmount { index: 0, amount: 1 },
+            PlanPatch::SetReleaseTime { index: 1, release_time: 1_600 },
+            PlanPatch::Remove { index: 1 },
+        ] {
+            let mut plans = original.clone();
+            assert!(error_is(patch.apply(&mut plans), VestingError::ReleasedPlanModified));
+        }
+        let mut plans = original.clone();
+        assert!(error_is(PlanPatch::Remove { index: 3 }.apply(&mut plans), VestingError::PlanIndexOutOfRange));
+        assert!(error_is(
+            PlanPatch::Insert { index: 4, release_time: 1_800, amount: 1 }.apply(&mut plans),
+            VestingError::PlanIndexOutOfRange
+        ));
+
+        let mut plans = original.clone();
+        PlanPatch::Shift { delta: -100 }.apply(&mut plans).unwrap();
+        assert!(plans[..2] == original[..2]);                // Paid plans keep their time
+        assert_eq!(plans[2].release_time, 1_900);
+
+        let mut plans = original.clone();
+        PlanPatch::SetAmount { index: 2, amount: 60 }.apply(&mut plans).unwrap();
+        PlanPatch::Insert { index: 3, release_time: 2_000, amount: 40 }.apply(&mut plans).unwrap();
+        assert_eq!(plans.len(), 4);
+        assert!(check_paid_plans_kept(&original, &plans).is_ok());
+    }
+
+    #[test]
+    fn patches_require_validation() {
+        assert!(error_is(ScheduleValidation::Skip.check_patch(), VestingError::ValidationRequired));
+        assert!(ScheduleValidation::Incremental.check_patch().is_ok());
+        assert!(ScheduleValidation::Strict.check_patch().is_ok());
+    }
+
+    #[test]
+    fn approvals_count_current_signers_only() {
+        let signers: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let config = ApprovalConfig { signers: signers.clone(), threshold: 2, proposal_count: 1 };
+        let operation = ProposalOperation::RemoveAdmin;
+        let mut proposal = Proposal {
+            id: 0,
+            proposer: signers[0],
+            operation: operation.clone(),
+            approvals: vec![signers[0]],
+            executed: false,
+        };
+        assert!(error_is(config.check_approved(&proposal, &operation), VestingError::ApprovalRequired));
+
+        proposal.approvals.push(Pubkey::new_unique());         // Removed signer
+        assert_eq!(config.approval_count(&proposal), 1);
+        assert!(error_is(config.check_approved(&proposal, &operation), VestingError::ApprovalRequired));
+
+        proposal.approvals.push(signers[2]);
+        assert!(config.check_approved(&proposal, &operation).is_ok());
+        assert!(error_is(
+            config.check_approved(&proposal, &ProposalOperation::RotateDeployer { new_deployer: signers[1] }),
+            VestingError::ProposalMismatch
+        ));
+        proposal.executed = true;
+        assert!(error_is(config.check_approved(&proposal, &operation), VestingError::ProposalAlreadyExecuted));
+    }
+
+    #[test]
+    fn consume_approval_marks_proposal_executed() {
+        let signers: Vec<Pubkey> = (0..2).map(|_| Pubkey::new_unique()).collect();
+        let operation = ProposalOperation::RemoveAdmin;
+        let program_id = crate::ID;
+
+        let (config_key, mut config_lamports, mut empty) = (Pubkey::new_unique(), 0u64, Vec::new());
+        let unset = AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut empty, &program_id, false, 0);
+        assert!(consume_approval(&unset, None, &operation).is_ok()); // Approvals not configured
+
+        let mut config_data = Vec::new();
+        ApprovalConfig { signers: signers.clone(), threshold: 2, proposal_count: 1 }
+            .try_serialize(&mut config_data)
+            .unwrap();
+        let mut config_lamports = 1;
+        let config = AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0);
+        assert!(error_is(consume_approval(&config, None, &operation), VestingError::ApprovalRequired));
+
+        let mut proposal_data = Vec::new();
+        Proposal { id: 0, proposer: signers[0], operation: operation.clone(), approvals: signers.clone(), executed: false }
+            .try_serialize(&mut proposal_data)
+            .unwrap();
+        let (proposal_key, mut proposal_lamports) = (Pubkey::new_unique(), 1u64);
+        let proposal_info = AccountInfo::new(
+            &proposal_key,
+            false,
+            true,
+            &mut proposal_lamports,
+            &mut proposal_data,
+            &program_id,
+            false,
+            0,
+        );
+        let mut proposal = Account::<Proposal>::try_from(&proposal_info).unwrap();
+        consume_approval(&config, Some(&mut proposal), &operation).unwrap();
+        assert!(proposal.executed);
+        assert!(error_is(
+            consume_approval(&config, Some(&mut proposal), &operation),
+            VestingError::ProposalAlreadyExecuted
+        ));
+    }
+
+    #[test]
+    fn batch_entries_split_by_extra_accounts() {
+        let accounts: Vec<u8> = (0..12).collect();
+        let groups = batch_entries(&accounts, &[1, 0, 2]).unwrap();
+        assert_eq!(groups, vec![&accounts[..4], &accounts[4..7], &accounts[7..]]);
+        assert!(error_is(batch_entries(&accounts, &[1, 0, 1]), VestingError::InvalidParameters)); // Leftover account
+        assert!(error_is(batch_entries(&accounts, &[1, 0, 3]), VestingError::InvalidParameters)); // Incomplete entry
+        assert!(error_is(batch_entries(&accounts, &[]), VestingError::InvalidParameters));
+    }
+
+    #[test]
+    fn matured_amount_previews_the_release() {
+        let v = vesting(300, 0);
+        let plans = vec![paid(1_000, 100, 100), paid(1_500, 100, 30), YearlyPlan::new(2_000, 100)];
+        assert_eq!(matured_amount(&v, &plans, 1_600).unwrap(), 70); // Only the unpaid rest of matured plans
+        assert_eq!(matured_amount(&v, &plans, 2_000).unwrap(), 170);
+        assert!(error_is(matured_amount(&v, &plans[..1], 2_000), VestingError::NoTokensToRelease)); // Skipped entry
+
+        let mut released = plans.clone();
+        assert_eq!(release_matured_plans(&mut released, 2_000).unwrap().0, 170); // Agrees with the real release
+    }
+
+    #[test]
+    fn skipped_entries_report_their_error_code() {
+        let err: Error = VestingError::NoTokensToRelease.into();
+        assert_eq!(error_code_of(&err), 6000 + VestingError::NoTokensToRelease as u32);
+        let err: Error = ProgramError::InsufficientFunds.into();
+        assert_ne!(error_code_of(&err), 0);                   // Never reported as success
+        let err: Error = ProgramError::Custom(1).into();
+        assert_eq!(error_code_of(&err), 1);
+    }
+
+    #[test]
+    fn legacy_vesting_accounts_migrate_to_the_current_layout() {
+        let mut current = vesting(300, 100);
+        current.plan_chunk_count = 0;
+        let mut data = Vec::new();
+        current.try_serialize(&mut data).unwrap();
+        data.truncate(data.len() - (1 + 8 + 1 + 2));            // Drop kind, cliff_time, destination_policy, plan_chunk_count
+        data.resize(LEGACY_VESTING_ACCOUNT_SPACE, 0);            // Category padding
+
+        let migrated = migrated_vesting_account(&data).unwrap();
+        assert_eq!(migrated.beneficiary, current.beneficiary);
+        assert_eq!(migrated.released_amount, 100);
+        assert_eq!(migrated.category, "Team");
+        assert_eq!(migrated.parent_vault, current.parent_vault);
+        assert!(migrated.kind == VestingKind::Plan && migrated.cliff_time == 0 && migrated.plan_chunk_count == 0);
+
+        let mut full = Vec::new();
+        current.try_serialize(&mut full).unwrap();
+        full.resize(VESTING_ACCOUNT_SPACE, 0);
+        assert!(error_is(migrated_vesting_account(&full), VestingError::InvalidParameters)); // Already migrated
+        data[0] ^= 1;
+        assert!(error_is(migrated_vesting_account(&data), VestingError::InvalidParameters)); // Not a VestingAccount
+    }
+
+    #[test]
+    fn partial_releases_track_plan_payouts() {
+        let mut plans = vec![YearlyPlan::new(1_000, 100), YearlyPlan::new(2_000, 100)];
+        let plan = find_matured_plan(&mut plans, 1_000, 1_500).unwrap();
+        plan.release(30).unwrap();                                 // Legal hold on the rest
+        assert!(!plan.released && plan.remaining_amount() == 70);
+        assert!(error_is(plan.release(71), VestingError::InvalidParameters)); // More than is left
+        assert!(error_is(plan.release(0), VestingError::InvalidParameters));
+
+        let plan = find_matured_plan(&mut plans, 1_000, 1_500).unwrap(); // Still releasable
+        plan.release(70).unwrap();
+        assert!(plan.released && plan.released_amount == 100);
+        assert!(error_is(find_matured_plan(&mut plans, 1_000, 1_500), VestingError::AlreadyReleased));
+        assert!(error_is(find_matured_plan(&mut plans, 2_000, 1_500), VestingError::VestingNotReached));
+        assert!(error_is(find_matured_plan(&mut plans, 1_234, 2_500), VestingError::InvalidParameters));
+
+        let mut partly = YearlyPlan::new(3_000, 100);
+        partly.release(40).unwrap();
+        assert_eq!(partly.release_remaining(), 60);                // Catch-up pays only the rest
+        assert!(partly.released);
+    }
+
+    #[test]
+    fn legacy_plan_chunks_migrate_with_full_payouts() {
+        let vesting_account = Pubkey::new_unique();
+        let mut data = VestingPlanChunk::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(vesting_account.as_ref());
+        data.extend_from_slice(&2u32.to_le_bytes());
+        for (release_time, amount, released) in [(1_000i64, 100u64, true), (2_000, 200, false)] {
+            data.extend_from_slice(&release_time.to_le_bytes());
+            data.extend_from_slice(&amount.to_le_bytes());
+            data.push(u8::from(released));
+        }
+        data.resize(LEGACY_PLAN_CHUNK_SPACE, 0);
+
+        let migrated = migrated_plan_chunk(&data).unwrap();
+        assert_eq!(migrated.vesting_account, vesting_account);
+        assert!(migrated.plans == vec![paid(1_000, 100, 100), YearlyPlan::new(2_000, 200)]);
+
+        data.push(0);
+        assert!(error_is(migrated_plan_chunk(&data), VestingError::InvalidParameters)); // Not the legacy size
+    }
+
+    #[test]
+    fn destination_policy_always_admits_the_beneficiary_ata() {
+        let mut v = vesting(300, 0);
+        let mint_wallet = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let ata = get_associated_token_address(&v.beneficiary, &v.token_mint);
+        let wallet_ata = get_associated_token_address(&mint_wallet, &v.token_mint);
+        let token_program = anchor_spl::token::ID;
+        let (mut l1, mut l2, mut l3, mut l4) = (1u64, 1u64, 1u64, 1u64);
+        let mut ata_data = token_account_data(&v.token_mint, &v.beneficiary, 0);
+        let mut wallet_data = token_account_data(&v.token_mint, &mint_wallet, 0);
+        let mut other_data = token_account_data(&v.token_mint, &other, 0);
+        let ata_info = AccountInfo::new(&ata, false, true, &mut l1, &mut ata_data, &token_program, false, 0);
+        let wallet_info = AccountInfo::new(&wallet_ata, false, true, &mut l2, &mut wallet_data, &token_program, false, 0);
+        let other_info = AccountInfo::new(&other, false, true, &mut l3, &mut other_data, &token_program, false, 0);
+        let beneficiary_ata = Account::<TokenAccount>::try_from(&ata_info).unwrap();
+        let mint_wallet_ata = Account::<TokenAccount>::try_from(&wallet_info).unwrap();
+        let listed = Account::<TokenAccount>::try_from(&other_info).unwrap();
+
+        let allowlist_key = Pubkey::new_unique();
+        let program_id = crate::ID;
+        let mut allowlist_data = account_data(&DestinationAllowlist {
+            vesting_account: Pubkey::new_unique(),
+            destinations: vec![other],
+        });
+        let allowlist_info = AccountInfo::new(&allowlist_key, false, false, &mut l4, &mut allowlist_data, &program_id, false, 0);
+        let allowlist = Account::<DestinationAllowlist>::try_from(&allowlist_info).unwrap();
+
+        assert!(check_destination(&beneficiary_ata, &v, &mint_wallet, None).is_ok());
+        assert!(check_destination(&mint_wallet_ata, &v, &mint_wallet, None).is_ok());
+        assert!(error_is(
+            check_destination(&listed, &v, &mint_wallet, Some(&allowlist)), // Allowlist unused under this policy
+            VestingError::DestinationNotAllowed
+        ));
+
+        v.destination_policy = DestinationPolicy::BeneficiaryOnly;
+        assert!(check_destination(&beneficiary_ata, &v, &mint_wallet, None).is_ok());
+        assert!(error_is(check_destination(&mint_wallet_ata, &v, &mint_wallet, None), VestingError::DestinationNotAllowed));
+
+        v.destination_policy = DestinationPolicy::Allowlist;
+        assert!(check_destination(&listed, &v, &mint_wallet, Some(&allowlist)).is_ok());
+        assert!(check_destination(&beneficiary_ata, &v, &mint_wallet, Some(&allowlist)).is_ok()); // Not listed, still allowed
+        assert!(check_destination(&beneficiary_ata, &v, &mint_wallet, None).is_ok());
+        assert!(error_is(check_destination(&listed, &v, &mint_wallet, None), VestingError::DestinationNotAllowed));
+        assert!(error_is(
+            check_destination(&mint_wallet_ata, &v, &mint_wallet, Some(&allowlist)),
+            VestingError::DestinationNotAllowed
+        ));
+    }
+
+    #[test]
+    fn crank_tip_keeps_the_pool_rent_exempt() {
+        let (pool_key, cranker_key, program_id, system) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), crate::ID, system_program::ID);
+        let (mut pool_lamports, mut cranker_lamports) = (1_000 + 250u64, 10u64);
+        let (mut pool_data, mut cranker_data) = (vec![0u8; TIP_POOL_SPACE], Vec::new());
+        let pool = AccountInfo::new(&pool_key, false, true, &mut pool_lamports, &mut pool_data, &program_id, false, 0);
+        let cranker = AccountInfo::new(&cranker_key, true, true, &mut cranker_lamports, &mut cranker_data, &system, false, 0);
+
+        assert_eq!(pay_crank_tip(&pool, &cranker, 100, 1_000).unwrap(), 100);
+        assert_eq!(pay_crank_tip(&pool, &cranker, 100, 1_000).unwrap(), 100);
+        assert_eq!(pool.lamports(), 1_050);
+        assert_eq!(cranker.lamports(), 210);
+        assert_eq!(pay_crank_tip(&pool, &cranker, 100, 1_000).unwrap(), 0); // Would dip below rent: no tip, no error
+        assert_eq!(pay_crank_tip(&pool, &cranker, 0, 1_000).unwrap(), 0);   // Tips disabled
+        assert_eq!(pool.lamports(), 1_050);
+        assert_eq!(cranker.lamports(), 210);
+    }
+
+    #[test]
+    fn vesting_info_summarises_plans() {
+        let mut v = vesting(300, 100);
+        let plans = vec![paid(1_000, 100, 100), paid(1_500, 100, 40), YearlyPlan::new(2_000, 100)];
+        let info = v.info(&plans, 1_600).unwrap();
+        assert_eq!((info.total_amount, info.released_amount), (300, 100));
+        assert_eq!((info.releasable_amount, info.next_release_time), (60, 2_000));
+        assert!(info.is_active);
+
+        let info = v.info(&plans, 2_000).unwrap();
+        assert_eq!((info.releasable_amount, info.next_release_time), (160, 0)); // Nothing left to schedule
+
+        v.cliff_time = 1_800;                                      // Matured plans wait for the cliff
+        let info = v.info(&plans, 1_600).unwrap();
+        assert_eq!((info.releasable_amount, info.next_release_time), (0, 1_800));
+    }
+
+    #[test]
+    fn vesting_info_streams_linear_vestings() {
+        let mut v = vesting(1_000, 200);
+        v.kind = VestingKind::Linear;
+        let info = v.info(&[], 1_500).unwrap();
+        assert_eq!((info.releasable_amount, info.next_release_time), (300, 1_500));
+        let info = v.info(&[], 500).unwrap();
+        assert_eq!((info.releasable_amount, info.next_release_time), (0, 1_000)); // First unlock
+        v.released_amount = 1_000;
+        assert_eq!(v.info(&[], 3_000).unwrap().next_release_time, 0); // Fully paid out
+    }
+
+    #[test]
+    fn plan_pages_load_and_release_together() {
+        let mut v = vesting(300, 0);
+        v.plan_chunk_count = 2;
+        let vesting_key = Pubkey::new_unique();
+        let vesting_info = leaked_infos(vec![leaked_account(vesting_key, account_data(&v), true)]);
+        let vesting_account = Account::<VestingAccount>::try_from(&vesting_info[0]).unwrap();
+
+        let page = |plans: Vec<YearlyPlan>, owner: Pubkey, writable: bool| {
+            leaked_account(Pubkey::new_unique(), account_data(&VestingPlanChunk { vesting_account: owner, plans }), writable)
+        };
+        let first = page(vec![YearlyPlan::new(1_000, 100), YearlyPlan::new(1_500, 100)], vesting_key, true);
+        let second = page(vec![YearlyPlan::new(2_000, 100)], vesting_key, true);
+        let foreign = page(vec![YearlyPlan::new(2_000, 100)], Pubkey::new_unique(), true);
+        let read_only = page(vec![YearlyPlan::new(2_000, 100)], vesting_key, false);
+
+        let load = |infos: Vec<AccountInfo<'static>>, writable: bool| {
+            PlanStore::load(&vesting_account, leaked_infos(infos), writable).map(|_| ())
+        };
+        assert!(error_is(load(vec![first.clone()], false), VestingError::PlanChunkMismatch)); // Missing page
+        assert!(error_is(load(vec![first.clone(), first.clone()], false), VestingError::PlanChunkMismatch));
+        assert!(error_is(load(vec![first.clone(), foreign], false), VestingError::PlanChunkMismatch));
+        assert!(error_is(load(vec![first.clone(), read_only.clone()], true), VestingError::PlanChunkMismatch));
+        assert!(load(vec![first.clone(), read_only], false).is_ok());
+        assert!(error_is(
+            load(vec![first.clone(), second.clone(), second.clone()], false), // Trailing account
+            VestingError::PlanChunkMismatch
+        ));
+
+        let pages = leaked_infos(vec![first, second]);
+        let mut store = PlanStore::load(&vesting_account, pages, true).unwrap();
+        assert_eq!(store.plans().unwrap().len(), 3);
+        let (amount, release_times) = release_due(&vesting_account, &mut store, 2_000).unwrap();
+        assert_eq!((amount, release_times), (300, vec![1_000, 1_500, 2_000])); // Every page is settled
+        store.exit(&crate::ID).unwrap();
+
+        let mut store = PlanStore::load(&vesting_account, pages, true).unwrap();
+        assert!(store.plans().unwrap().iter().all(|p| p.released)); // Flags were written back to each page
+        assert!(error_is(release_due(&vesting_account, &mut store, 3_000), VestingError::NoTokensToRelease));
+    }
+
+    #[test]
+    fn plan_page_addresses_are_per_index() {
+        let vesting_key = Pubkey::new_unique();
+        let (expected, _) =
+            Pubkey::find_program_address(&[b"plans", vesting_key.as_ref(), &1u16.to_le_bytes()], &crate::ID);
+        assert_eq!(plan_page_address(&vesting_key, 1), expected);
+        assert_ne!(plan_page_address(&vesting_key, 0), expected);
+        assert_ne!(plan_page_address(&Pubkey::new_unique(), 1), expected);
+    }
+
+    #[test]
+    fn migrated_allocation_entries_name_parent_tranches() {
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let parent_pages = vec![
+            (first, vec![YearlyPlan::new(1_000, 500), YearlyPlan::new(2_000, 500)]),
+            (second, vec![YearlyPlan::new(3_000, 500)]),
+        ];
+        let entry = |release_time, amount, parent_plan_chunk| AllocationEntry { release_time, amount, parent_plan_chunk };
+
+        assert!(check_allocation_entries(&[entry(2_000, 200, first), entry(3_000, 100, second)], &parent_pages).is_ok());
+        assert!(error_is(
+            check_allocation_entries(&[entry(3_000, 100, first)], &parent_pages), // Tranche is on the other page
+            VestingError::UnmatchedAllocation
+        ));
+        assert!(error_is(
+            check_allocation_entries(&[entry(1_000, 100, Pubkey::new_unique())], &parent_pages), // Page not passed
+            VestingError::PlanChunkMismatch
+        ));
+        assert!(error_is(check_allocation_entries(&[entry(1_000, 0, first)], &parent_pages), VestingError::InvalidParameters));
+    }
+
+    #[test]
+    fn revoking_a_child_refunds_only_unpaid_tranches() {
+        let chunk = Pubkey::new_unique();
+        let entry = |release_time, amount| AllocationEntry { release_time, amount, parent_plan_chunk: chunk };
+        let entries = vec![entry(1_000, 100), entry(2_000, 100), entry(3_000, 50)];
+        let child_plans = vec![paid(1_000, 100, 100), paid(2_000, 100, 30), YearlyPlan::new(3_000, 50)];
+
+        let refunds = allocation_refunds(&entries, &child_plans);
+        assert_eq!(refunds.iter().map(|(e, unpaid)| (e.release_time, *unpaid)).collect::<Vec<_>>(), vec![(2_000, 70), (3_000, 50)]);
+
+        let mut parent_plans = vec![paid(2_000, 400, 400), paid(3_000, 200, 150)]; // Deducted tranches at the child's times
+        for (entry, unpaid) in refunds {
+            restore_parent_plan(&mut parent_plans, entry.release_time, unpaid).unwrap();
+        }
+        assert_eq!((parent_plans[0].amount, parent_plans[0].released), (470, false)); // Paid-out parent tranche reopens
+        assert_eq!(parent_plans[1].amount, 250);
+        assert!(error_is(restore_parent_plan(&mut parent_plans, 9_000, 1), VestingError::UnmatchedAllocation));
+    }
+
+    #[test]
+    fn child_plans_are_locked_to_their_allocation() {
+        let mut child = vesting(500, 0);
+        assert!(error_is(check_plans_unallocated(&child), VestingError::AllocatedPlansLocked));
+        child.parent_vault = child.token_vault;                        // Top-level vesting funds itself
+        assert!(check_plans_unallocated(&child).is_ok());
+    }
+
+    #[test]
+    fn parent_deductions_are_recorded_per_tranche() {
+        let source = Pubkey::new_unique();
+        let mut parent_plans = vec![YearlyPlan::new(1_000, 500), paid(2_000, 500, 500), YearlyPlan::new(3_000, 500)];
+        let mut entries = Vec::new();
+
+        deduct_parent_plans(&mut parent_plans, &[YearlyPlan::new(1_000, 200), paid(3_000, 100, 100)], source, &mut entries).unwrap();
+        assert_eq!(parent_plans[0].amount, 300);
+        assert_eq!(parent_plans[2].amount, 500);                       // Paid-out child plans take nothing
+        assert_eq!(entries.iter().map(|e| (e.release_time, e.amount, e.parent_plan_chunk)).collect::<Vec<_>>(), vec![(1_000, 200, source)]);
+
+        assert!(error_is(
+            deduct_parent_plans(&mut parent_plans, &[YearlyPlan::new(2_000, 1)], source, &mut entries), // Parent tranche already paid
+            VestingError::UnmatchedAllocation
+        ));
+        assert!(error_is(
+            deduct_parent_plans(&mut parent_plans, &[YearlyPlan::new(1_000, 301)], source, &mut entries),
+            VestingError::InsufficientAmount
+        ));
+    }
+
+    #[test]
+    fn zero_copy_chunk_holds_the_migrated_pages() {
+        let pages = [vec![paid(1_000, 100, 40), YearlyPlan::new(2_000, 100)], vec![YearlyPlan::new(3_000, 100)]];
+        let plans: Vec<YearlyPlan> = pages.iter().flatten().cloned().collect(); // Page order, as migrate_to_zero_copy_plans reads them
+        let mut chunk: ZeroCopyPlanChunk = bytemuck::Zeroable::zeroed();
+
+        chunk.set_plans(&plans).unwrap();
+        assert!(chunk.to_plans() == plans);
+        chunk.set_plans(&plans[..1]).unwrap();
+        assert!(chunk.to_plans() == plans[..1]);
+        assert_eq!(chunk.plans[1].release_time, 0);                    // Dropped slots are cleared
+        assert!(error_is(
+            chunk.set_plans(&vec![YearlyPlan::new(1_000, 1); ZERO_COPY_MAX_PLANS + 1]),
+            VestingError::InsufficientSpace
+        ));
+    }
+
+    #[test]
+    fn shorter_timelock_delays_apply_after_the_old_notice() {
+        let timelock = PlanTimelock { delay: 3_600, pending_delay: 60, pending_delay_at: 10_000 };
+        assert_eq!(timelock.delay_at(9_999), 3_600);
+        assert_eq!(timelock.delay_at(10_000), 60);
+        assert_eq!(PlanTimelock { pending_delay_at: 0, ..timelock }.delay_at(20_000), 3_600); // Nothing pending
+    }
 }
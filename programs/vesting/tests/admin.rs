@@ -0,0 +1,124 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use common::*;
+use vesting::{accounts, instruction, AdminConfig, DeployAdmin, VestingError};
+
+fn with_deployer(s: &mut Setup) -> Pubkey {
+    let deployer = Pubkey::new_unique();
+    s.bank.fund(deployer, 1_000_000_000);
+    s.bank.set_account(pda(&[b"deploy_admin"]), &DeployAdmin { deployer }, 0);
+    deployer
+}
+
+fn remove_admin(s: &mut Setup, deployer: Pubkey) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::RemoveAdmin {
+        deployer,
+        deployer_admin: pda(&[b"deploy_admin"]),
+        admin: s.admin,
+        admin_config: s.admin_config,
+        approval_config: pda(&[b"approvals"]),
+        proposal: None,
+    };
+    s.bank.process(accounts, &[], instruction::RemoveAdmin {})
+}
+
+fn initialize(s: &mut Setup, deployer: Pubkey, admin: Pubkey) -> std::result::Result<(), ProgramError> {
+    s.bank.fund(admin, 1_000_000_000);
+    let accounts = accounts::Initialize {
+        deployer,
+        deployer_admin: pda(&[b"deploy_admin"]),
+        admin,
+        admin_config: s.admin_config,
+        system_program: system_program::ID,
+    };
+    s.bank.process(accounts, &[], instruction::Initialize {})
+}
+
+fn propose(s: &mut Setup, proposer: Pubkey, new_admin: Pubkey) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::ProposeAdmin {
+        proposer,
+        deployer_admin: pda(&[b"deploy_admin"]),
+        admin_config: s.admin_config,
+        approval_config: pda(&[b"approvals"]),
+        proposal: None,
+    };
+    s.bank.process(accounts, &[], instruction::ProposeAdmin { new_admin })
+}
+
+fn accept(s: &mut Setup, new_admin: Pubkey) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::AcceptAdmin { new_admin, admin_config: s.admin_config };
+    s.bank.process(accounts, &[], instruction::AcceptAdmin {})
+}
+
+#[test]
+fn reinitializing_keeps_the_seed_admin() {
+    let mut s = setup();
+    let deployer = with_deployer(&mut s);
+    let (first_admin, next_admin) = (s.admin, Pubkey::new_unique());
+
+    assert_eq!(initialize(&mut s, deployer, next_admin), Err(vesting_error(VestingError::AdminAlreadySet)));
+    remove_admin(&mut s, deployer).unwrap();
+    let cleared: AdminConfig = s.bank.get(&s.admin_config);
+    assert_eq!((cleared.admin, cleared.seed_admin), (Pubkey::default(), first_admin)); // Account stays
+
+    initialize(&mut s, deployer, next_admin).unwrap();
+    let config: AdminConfig = s.bank.get(&s.admin_config);
+    assert_eq!((config.admin, config.seed_admin), (next_admin, first_admin)); // Vault PDAs still resolve
+}
+
+#[test]
+fn only_the_deployer_removes_the_admin() {
+    let mut s = setup();
+    with_deployer(&mut s);
+    let admin = s.admin;
+    assert_eq!(remove_admin(&mut s, admin), Err(vesting_error(VestingError::NotDeployAdmin)));
+    assert_eq!(s.bank.get::<AdminConfig>(&s.admin_config).admin, admin);
+}
+
+#[test]
+fn admin_handover_takes_two_steps() {
+    let mut s = setup();
+    let deployer = with_deployer(&mut s);
+    let (admin, new_admin, stranger) = (s.admin, Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_eq!(propose(&mut s, stranger, stranger), Err(vesting_error(VestingError::Unauthorized)));
+    assert_eq!(accept(&mut s, new_admin), Err(vesting_error(VestingError::Unauthorized))); // Nothing proposed yet
+
+    propose(&mut s, admin, new_admin).unwrap();
+    assert_eq!(s.bank.get::<AdminConfig>(&s.admin_config).admin, admin); // Still in charge until accepted
+    assert_eq!(accept(&mut s, stranger), Err(vesting_error(VestingError::Unauthorized)));
+    accept(&mut s, new_admin).unwrap();
+    let config: AdminConfig = s.bank.get(&s.admin_config);
+    assert_eq!((config.admin, config.pending_admin, config.seed_admin), (new_admin, Pubkey::default(), admin));
+
+    propose(&mut s, deployer, admin).unwrap();                     // The deployer may propose too
+    accept(&mut s, admin).unwrap();
+    assert_eq!(s.bank.get::<AdminConfig>(&s.admin_config).admin, admin);
+}
+
+#[test]
+fn legacy_admin_config_migrates_in_place() {
+    let mut s = setup();
+    let deployer = with_deployer(&mut s);
+    let admin = s.admin;
+    let mut legacy = AdminConfig::DISCRIMINATOR.to_vec();
+    legacy.extend_from_slice(admin.as_ref());                          // AdminConfig before admin handover
+    s.bank.set(s.admin_config, vesting::ID, rent_exempt(8 + 32 * 3), legacy); // Prepaid, so no top-up transfer is needed
+
+    let migrate = |s: &mut Setup, deployer| {
+        let accounts = accounts::MigrateAdminConfig {
+            deployer,
+            deployer_admin: pda(&[b"deploy_admin"]),
+            admin_config: s.admin_config,
+            system_program: system_program::ID,
+        };
+        s.bank.process(accounts, &[], instruction::MigrateAdminConfig {})
+    };
+    assert_eq!(migrate(&mut s, admin), Err(vesting_error(VestingError::NotDeployAdmin)));
+    migrate(&mut s, deployer).unwrap();
+    let config: AdminConfig = s.bank.get(&s.admin_config);
+    assert_eq!((config.admin, config.pending_admin, config.seed_admin), (admin, Pubkey::default(), admin));
+    assert_eq!(migrate(&mut s, deployer), Err(vesting_error(VestingError::InvalidParameters))); // Already migrated
+}
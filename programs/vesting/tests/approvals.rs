@@ -0,0 +1,139 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token;
+use common::*;
+use vesting::{
+    accounts, instruction, ApprovalConfig, PlanTimelock, Proposal, ProposalOperation, Role, Roles, TokenInfo, VestingAccount,
+    VestingError,
+};
+
+const ROLES_SPACE: usize = 8 + 5 * (4 + 4 * 32);
+const PLAN_TIMELOCK_SPACE: usize = 8 + 8 + 8 + 8;
+
+// Two approvers, both needed
+fn set_approvals(s: &mut Setup) -> Vec<Pubkey> {
+    let signers = vec![s.admin, Pubkey::new_unique()];
+    let config = ApprovalConfig { signers: signers.clone(), threshold: 2, proposal_count: 1 };
+    s.bank.set_account(pda(&[b"approvals"]), &config, 0);
+    signers
+}
+
+fn set_proposal(s: &mut Setup, operation: ProposalOperation, approvals: Vec<Pubkey>) -> Pubkey {
+    let key = Pubkey::new_unique();
+    let proposal = Proposal { id: 0, proposer: approvals[0], operation, approvals, executed: false };
+    s.bank.set_account(key, &proposal, 0);
+    key
+}
+
+fn set_members(s: &mut Setup, members: Vec<Pubkey>, proposal: Option<Pubkey>) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::SetRoleMembers {
+        admin: s.admin,
+        admin_config: s.admin_config,
+        roles: pda(&[b"roles"]),
+        approval_config: pda(&[b"approvals"]),
+        proposal,
+        system_program: system_program::ID,
+    };
+    s.bank.process(accounts, &[], instruction::SetRoleMembers { role: Role::Pauser, members })
+}
+
+fn set_timelock(s: &mut Setup, delay: i64, proposal: Option<Pubkey>) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::SetPlanTimelock {
+        admin: s.admin,
+        admin_config: s.admin_config,
+        plan_timelock: pda(&[b"plan_timelock"]),
+        approval_config: pda(&[b"approvals"]),
+        proposal,
+        system_program: system_program::ID,
+    };
+    s.bank.process(accounts, &[], instruction::SetPlanTimelock { delay })
+}
+
+#[test]
+fn role_changes_need_an_approved_proposal() {
+    let mut s = setup();
+    let roles = Roles { schedulers: vec![], plan_editors: vec![], pausers: vec![], closers: vec![], token_registrars: vec![] };
+    s.bank.set_account(pda(&[b"roles"]), &roles, ROLES_SPACE);
+    let signers = set_approvals(&mut s);
+    let pauser = Pubkey::new_unique();
+
+    assert_eq!(set_members(&mut s, vec![pauser], None), Err(vesting_error(VestingError::ApprovalRequired)));
+    let other_members = vec![s.admin];
+    let other = set_proposal(&mut s, ProposalOperation::SetRoleMembers { role: Role::Pauser, members: other_members }, signers.clone());
+    assert_eq!(set_members(&mut s, vec![pauser], Some(other)), Err(vesting_error(VestingError::ProposalMismatch)));
+
+    let approved = set_proposal(&mut s, ProposalOperation::SetRoleMembers { role: Role::Pauser, members: vec![pauser] }, signers);
+    set_members(&mut s, vec![pauser], Some(approved)).unwrap();
+    assert!(s.bank.get::<Roles>(&pda(&[b"roles"])).pausers == vec![pauser]);
+    assert!(s.bank.get::<Proposal>(&approved).executed);
+}
+
+#[test]
+fn timelock_changes_need_an_approved_proposal() {
+    let mut s = setup();
+    let timelock = pda(&[b"plan_timelock"]);
+    s.bank.set_account(timelock, &PlanTimelock { delay: 0, pending_delay: 0, pending_delay_at: 0 }, PLAN_TIMELOCK_SPACE);
+    let signers = set_approvals(&mut s);
+
+    assert_eq!(set_timelock(&mut s, 3_600, None), Err(vesting_error(VestingError::ApprovalRequired)));
+    let half_approved = set_proposal(&mut s, ProposalOperation::SetPlanTimelock { delay: 3_600 }, signers[..1].to_vec());
+    assert_eq!(set_timelock(&mut s, 3_600, Some(half_approved)), Err(vesting_error(VestingError::ApprovalRequired)));
+
+    let approved = set_proposal(&mut s, ProposalOperation::SetPlanTimelock { delay: 3_600 }, signers);
+    set_timelock(&mut s, 3_600, Some(approved)).unwrap();
+    assert_eq!(s.bank.get::<PlanTimelock>(&timelock).delay, 3_600);
+}
+
+#[test]
+fn crank_pays_only_the_beneficiary_ata_under_approvals() {
+    let mut s = setup();
+    let (mint, mint_wallet, token_vault) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let vault_authority = pda(&[b"vault_auth", s.admin.as_ref(), token_vault.as_ref()]);
+    let mint_wallet_ata = get_associated_token_address(&mint_wallet, &mint); // Allowed by the destination policy
+    let mut vesting_account: VestingAccount = s.bank.get(&s.vesting_account);
+    vesting_account.token_mint = mint;
+    vesting_account.token_vault = token_vault;
+    vesting_account.parent_vault = token_vault;
+    vesting_account.destination_token_account = mint_wallet_ata;
+    s.bank.set_account(s.vesting_account, &vesting_account, 0);
+    s.bank.set_mint(mint, 6);
+    s.bank.set_token_account(token_vault, mint, vault_authority, 0);
+    s.bank.set_token_account(vesting_account.beneficiary_vault, mint, vault_authority, 1_000);
+    s.bank.set_token_account(mint_wallet_ata, mint, mint_wallet, 0);
+    let token_info = pda(&[b"token_info", s.admin.as_ref(), mint.as_ref()]);
+    let info = TokenInfo {
+        token_name: String::from("Test"),
+        token_symbol: String::from("TST"),
+        total_supply: 1_000_000,
+        token_mint: mint,
+        mint_wallet_address: mint_wallet,
+    };
+    s.bank.set_account(token_info, &info, 0);
+
+    let crank = |s: &mut Setup| {
+        let cranker = Pubkey::new_unique();
+        s.bank.fund(cranker, 1_000_000_000);
+        let accounts = accounts::CrankRelease {
+            cranker,
+            admin_config: s.admin_config,
+            vesting_account: s.vesting_account,
+            destination_allowlist: None,
+            approval_config: pda(&[b"approvals"]),
+            token_info,
+            token_vault,
+            vault_authority,
+            beneficiary_vault: vesting_account.beneficiary_vault,
+            destination_token_account: mint_wallet_ata,
+            tip_pool: None,
+            token_mint: mint,
+            token_program: token::ID,
+        };
+        s.bank.process(accounts, &[], instruction::CrankRelease {})
+    };
+    assert_eq!(crank(&mut s), Err(vesting_error(VestingError::InvalidParameters))); // Destination accepted; no plan pages passed
+    set_approvals(&mut s);
+    assert_eq!(crank(&mut s), Err(vesting_error(VestingError::ApprovalRequired)));
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use common::*;
+use vesting::{accounts, instruction, PlanPatch, ScheduleValidation, VestingAccount, VestingError, VestingPlanChunk, YearlyPlan};
+
+// A child vesting funded from another vesting's vault, with its ledger-backed page
+fn setup_child(plans: Vec<YearlyPlan>) -> (Setup, Pubkey) {
+    let mut s = setup();
+    let mut vesting_account: VestingAccount = s.bank.get(&s.vesting_account);
+    vesting_account.parent_vault = Pubkey::new_unique();
+    vesting_account.plan_chunk_count = 1;
+    s.bank.set_account(s.vesting_account, &vesting_account, 0);
+    let page = set_plan_page(&mut s.bank, &s.vesting_account, 0, plans);
+    (s, page)
+}
+
+#[test]
+fn child_plans_cannot_be_replaced() {
+    let (mut s, page) = setup_child(vec![plan(2_000, 500)]);
+    let accounts = accounts::UpdatePlanChunk {
+        vesting_account: s.vesting_account,
+        plan_chunk: page,
+        admin: s.admin,
+        admin_config: s.admin_config,
+        roles: None,
+        plan_timelock: pda(&[b"plan_timelock"]),
+        system_program: system_program::ID,
+        approval_config: pda(&[b"approvals"]),
+        proposal: None,
+    };
+    let args = instruction::UpdatePlanChunk {
+        chunk_index: 0,
+        plans: vec![plan(2_000, 900)],
+        validation: ScheduleValidation::Incremental,
+        allow_amount_change: true,
+    };
+    assert_eq!(s.bank.process(accounts, &[], args), Err(vesting_error(VestingError::AllocatedPlansLocked)));
+    assert!(s.bank.get::<VestingPlanChunk>(&page).plans == vec![plan(2_000, 500)]);
+}
+
+#[test]
+fn child_plans_cannot_be_patched() {
+    let (mut s, page) = setup_child(vec![plan(2_000, 500)]);
+    let accounts = accounts::PatchPlanChunk {
+        vesting_account: s.vesting_account,
+        plan_chunk: page,
+        admin: s.admin,
+        admin_config: s.admin_config,
+        roles: None,
+        plan_timelock: pda(&[b"plan_timelock"]),
+        system_program: system_program::ID,
+        approval_config: pda(&[b"approvals"]),
+        proposal: None,
+    };
+    let args = instruction::PatchPlanChunk {
+        chunk_index: 0,
+        patch: PlanPatch::SetReleaseTime { index: 0, release_time: 3_000 },
+        validation: ScheduleValidation::Incremental,
+    };
+    assert_eq!(s.bank.process(accounts, &[], args), Err(vesting_error(VestingError::AllocatedPlansLocked)));
+}
@@ -0,0 +1,351 @@
+// Minimal in-process runtime for instruction-level tests: accounts are laid out as the loader serializes
+// them and vesting::entry runs on that buffer, with Clock/Rent served by syscall stubs.
+// Host builds cannot make CPIs, so tests stick to paths that create and move no tokens or lamports by CPI.
+#![allow(dead_code)]
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{bpf_loader_upgradeable, entrypoint::deserialize, program_pack::Pack, sysvar};
+use anchor_lang::{system_program, InstructionData};
+use anchor_spl::token::spl_token::{
+    self,
+    state::{Account as SplAccount, AccountState, Mint as SplMint},
+};
+use solana_sysvar::program_stubs::{self, SyscallStubs};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::Once;
+use vesting::VestingError;
+
+const MAX_PERMITTED_DATA_INCREASE: usize = 10 * 1024;
+const NON_DUP_MARKER: u8 = u8::MAX;
+
+thread_local! {
+    static NOW: Cell<i64> = const { Cell::new(0) };
+}
+
+#[derive(Clone)]
+pub struct TestAccount {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+pub struct Bank {
+    accounts: HashMap<Pubkey, TestAccount>,
+    pub now: i64,
+}
+
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &vesting::ID).0
+}
+
+pub fn rent_exempt(len: usize) -> u64 {
+    Rent::default().minimum_balance(len)
+}
+
+// The ProgramError an instruction fails with for `error`
+pub fn vesting_error(error: VestingError) -> ProgramError {
+    Error::from(error).into()
+}
+
+pub fn anchor_error(error: ErrorCode) -> ProgramError {
+    Error::from(error).into()
+}
+
+pub fn program_data_address() -> Pubkey {
+    Pubkey::find_program_address(&[vesting::ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+impl Bank {
+    pub fn new() -> Self {
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            program_stubs::set_syscall_stubs(Box::new(Stubs));
+        });
+
+        let mut bank = Bank { accounts: HashMap::new(), now: 1_000 };
+        for program in [system_program::ID, spl_token::ID] {
+            bank.set(program, Pubkey::default(), 1, Vec::new());
+            bank.accounts.get_mut(&program).unwrap().executable = true;
+        }
+        let mut program = 2u32.to_le_bytes().to_vec();                // UpgradeableLoaderState::Program
+        program.extend_from_slice(program_data_address().as_ref());
+        bank.set(vesting::ID, bpf_loader_upgradeable::ID, 1, program);
+        bank.accounts.get_mut(&vesting::ID).unwrap().executable = true;
+        bank.set_upgrade_authority(None);
+
+        let rent = Rent::default();
+        let mut rent_data = rent.lamports_per_byte_year.to_le_bytes().to_vec();
+        rent_data.extend_from_slice(&rent.exemption_threshold.to_le_bytes());
+        rent_data.push(rent.burn_percent);
+        bank.set(sysvar::rent::ID, sysvar::ID, 1, rent_data);
+        bank
+    }
+
+    pub fn set(&mut self, key: Pubkey, owner: Pubkey, lamports: u64, data: Vec<u8>) {
+        self.accounts.insert(key, TestAccount { lamports, data, owner, executable: false });
+    }
+
+    // A rent-exempt program account holding `account`, padded to `space` bytes
+    pub fn set_account<T: AccountSerialize + Owner>(&mut self, key: Pubkey, account: &T, space: usize) {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data.resize(space.max(data.len()), 0);
+        self.set(key, T::owner(), rent_exempt(data.len()), data);
+    }
+
+    pub fn set_lamports(&mut self, key: &Pubkey, lamports: u64) {
+        self.accounts.get_mut(key).unwrap().lamports = lamports;
+    }
+
+    pub fn fund(&mut self, key: Pubkey, lamports: u64) {
+        self.set(key, system_program::ID, lamports, Vec::new());
+    }
+
+    pub fn set_upgrade_authority(&mut self, authority: Option<Pubkey>) {
+        let mut data = 3u32.to_le_bytes().to_vec();                   // UpgradeableLoaderState::ProgramData
+        data.extend_from_slice(&0u64.to_le_bytes());                  // slot
+        match authority {
+            Some(authority) => {
+                data.push(1);
+                data.extend_from_slice(authority.as_ref());
+            }
+            None => data.push(0),
+        }
+        self.set(program_data_address(), bpf_loader_upgradeable::ID, 1, data);
+    }
+
+    pub fn set_mint(&mut self, key: Pubkey, decimals: u8) {
+        let mut data = vec![0; SplMint::LEN];
+        SplMint { decimals, is_initialized: true, ..Default::default() }.pack_into_slice(&mut data);
+        self.set(key, spl_token::ID, rent_exempt(data.len()), data);
+    }
+
+    pub fn set_token_account(&mut self, key: Pubkey, mint: Pubkey, owner: Pubkey, amount: u64) {
+        let mut data = vec![0; SplAccount::LEN];
+        SplAccount { mint, owner, amount, state: AccountState::Initialized, ..Default::default() }
+            .pack_into_slice(&mut data);
+        self.set(key, spl_token::ID, rent_exempt(data.len()), data);
+    }
+
+    pub fn account(&self, key: &Pubkey) -> Option<&TestAccount> {
+        self.accounts.get(key)
+    }
+
+    pub fn get<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        T::try_deserialize(&mut &self.accounts[key].data[..]).unwrap()
+    }
+
+    pub fn exists(&self, key: &Pubkey) -> bool {
+        self.accounts.contains_key(key)
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.accounts.get(key).map_or(0, |a| a.lamports)
+    }
+
+    pub fn data_len(&self, key: &Pubkey) -> usize {
+        self.accounts[key].data.len()
+    }
+
+    pub fn token_balance(&self, key: &Pubkey) -> u64 {
+        SplAccount::unpack(&self.accounts[key].data).unwrap().amount
+    }
+
+    // Run one instruction; account changes are kept only if it succeeds
+    pub fn process(
+        &mut self,
+        accounts: impl ToAccountMetas,
+        remaining_accounts: &[AccountMeta],
+        args: impl InstructionData,
+    ) -> std::result::Result<(), ProgramError> {
+        let mut metas = accounts.to_account_metas(None);
+        metas.extend_from_slice(remaining_accounts);
+
+        let mut keys: Vec<Pubkey> = Vec::new();
+        let mut flags: HashMap<Pubkey, (bool, bool)> = HashMap::new();
+        for meta in &metas {
+            if !flags.contains_key(&meta.pubkey) {
+                keys.push(meta.pubkey);
+            }
+            let (signer, writable) = flags.entry(meta.pubkey).or_default();
+            *signer |= meta.is_signer;
+            *writable |= meta.is_writable;
+        }
+        let empty = TestAccount { lamports: 0, data: Vec::new(), owner: system_program::ID, executable: false };
+        let lamports_before: u64 = keys.iter().map(|k| self.lamports(k)).sum();
+
+        let mut input = Vec::new();
+        input.extend_from_slice(&(metas.len() as u64).to_le_bytes());
+        for (index, meta) in metas.iter().enumerate() {
+            let first = metas.iter().position(|m| m.pubkey == meta.pubkey).unwrap();
+            if first < index {
+                input.push(first as u8);                              // Duplicate of an earlier account
+                input.extend_from_slice(&[0; 7]);
+                continue;
+            }
+            let account = self.accounts.get(&meta.pubkey).unwrap_or(&empty);
+            let (signer, writable) = flags[&meta.pubkey];
+            input.extend_from_slice(&[NON_DUP_MARKER, signer as u8, writable as u8, account.executable as u8]);
+            input.extend_from_slice(&[0; 4]);                         // original_data_len, filled by deserialize
+            input.extend_from_slice(meta.pubkey.as_ref());
+            input.extend_from_slice(account.owner.as_ref());
+            input.extend_from_slice(&account.lamports.to_le_bytes());
+            input.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+            input.extend_from_slice(&account.data);
+            input.resize(input.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+            input.resize(input.len().next_multiple_of(8), 0);
+            input.extend_from_slice(&u64::MAX.to_le_bytes());         // rent_epoch
+        }
+        let data = args.data();
+        input.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        input.extend_from_slice(&data);
+        input.extend_from_slice(vesting::ID.as_ref());
+
+        let mut buffer = vec![0u64; input.len().div_ceil(8)];        // 8-byte aligned, as the loader provides
+        bytemuck::cast_slice_mut::<u64, u8>(&mut buffer)[..input.len()].copy_from_slice(&input);
+
+        NOW.with(|now| now.set(self.now));
+        let (program_id, infos, instruction_data) = unsafe { deserialize(buffer.as_mut_ptr() as *mut u8) };
+        vesting::entry(program_id, &infos, instruction_data)?;
+
+        let mut after = Vec::new();
+        for key in &keys {
+            let info = infos.iter().find(|i| i.key == key).unwrap();
+            let account = TestAccount {
+                lamports: info.lamports(),
+                data: info.data.borrow().to_vec(),
+                owner: *info.owner,
+                executable: info.executable,
+            };
+            let before = self.accounts.get(key).unwrap_or(&empty);
+            if !flags[key].1 {
+                assert!(
+                    before.lamports == account.lamports && before.data == account.data && before.owner == account.owner,
+                    "read-only account {key} was modified"
+                );
+            }
+            after.push((*key, account));
+        }
+        let lamports_after: u64 = after.iter().map(|(_, a)| a.lamports).sum();
+        assert_eq!(lamports_before, lamports_after, "lamports were created or destroyed");
+
+        for (key, account) in after {
+            if account.lamports == 0 {
+                self.accounts.remove(&key);                           // Garbage-collected at the end of the transaction
+            } else {
+                self.accounts.insert(key, account);
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_log_data(&self, _fields: &[&[u8]]) {}
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock { unix_timestamp: NOW.with(Cell::get), ..Clock::default() };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        0
+    }
+}
+
+pub fn readonly(pubkey: Pubkey) -> AccountMeta {
+    AccountMeta::new_readonly(pubkey, false)
+}
+
+pub fn writable(pubkey: Pubkey) -> AccountMeta {
+    AccountMeta::new(pubkey, false)
+}
+
+pub struct Setup {
+    pub bank: Bank,
+    pub admin: Pubkey,
+    pub admin_config: Pubkey,
+    pub vesting_account: Pubkey,
+}
+
+// An admin and one Plan vesting of 1_000 tokens running from 1_000 to 100_000, before any plans are added
+pub fn setup() -> Setup {
+    let mut bank = Bank::new();
+    let admin = Pubkey::new_unique();
+    bank.fund(admin, 10_000_000_000);
+    let admin_config = pda(&[b"admin"]);
+    bank.set_account(
+        admin_config,
+        &vesting::AdminConfig { admin, pending_admin: Pubkey::default(), seed_admin: admin },
+        0,
+    );
+    let vesting_account = Pubkey::new_unique();
+    bank.set_account(vesting_account, &plan_vesting(Pubkey::new_unique()), 0);
+    Setup { bank, admin, admin_config, vesting_account }
+}
+
+pub fn plan_vesting(token_vault: Pubkey) -> vesting::VestingAccount {
+    vesting::VestingAccount {
+        beneficiary: Pubkey::new_unique(),
+        total_amount: 1_000,
+        released_amount: 0,
+        start_time: 1_000,
+        end_time: 100_000,
+        last_release_time: 0,
+        token_mint: Pubkey::new_unique(),
+        token_vault,
+        beneficiary_vault: Pubkey::new_unique(),
+        category: String::from("Team"),
+        is_active: true,
+        destination_token_account: Pubkey::new_unique(),
+        parent_vault: token_vault,                                   // Not a child: no parent deduction
+        kind: vesting::VestingKind::Plan,
+        cliff_time: 0,
+        destination_policy: vesting::DestinationPolicy::BeneficiaryOrMintWallet,
+        plan_chunk_count: 0,
+    }
+}
+
+pub fn plan_page(vesting_account: &Pubkey, index: u16) -> Pubkey {
+    pda(&[b"plans", vesting_account.as_ref(), &index.to_le_bytes()])
+}
+
+// Store `plans` as page `index` of `vesting_account`, rent-exempt at exactly their size
+pub fn set_plan_page(bank: &mut Bank, vesting_account: &Pubkey, index: u16, plans: Vec<vesting::YearlyPlan>) -> Pubkey {
+    let page = plan_page(vesting_account, index);
+    bank.set_account(page, &vesting::VestingPlanChunk { vesting_account: *vesting_account, plans }, 0);
+    page
+}
+
+pub fn plan(release_time: i64, amount: u64) -> vesting::YearlyPlan {
+    vesting::YearlyPlan::new(release_time, amount)
+}
+
+pub fn zero_copy_chunk(vesting_account: &Pubkey) -> Pubkey {
+    pda(&[b"zc_plans", vesting_account.as_ref()])
+}
+
+// The zero-copy chunk migrate_to_zero_copy_plans would leave behind
+pub fn set_zero_copy_plans(bank: &mut Bank, vesting_account: &Pubkey, plans: &[vesting::YearlyPlan]) -> Pubkey {
+    let key = zero_copy_chunk(vesting_account);
+    let mut chunk: vesting::ZeroCopyPlanChunk = bytemuck::Zeroable::zeroed();
+    chunk.vesting_account = *vesting_account;
+    chunk.set_plans(plans).unwrap();
+    let mut data = vesting::ZeroCopyPlanChunk::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(bytemuck::bytes_of(&chunk));
+    bank.set(key, vesting::ID, rent_exempt(data.len()), data);
+    key
+}
+
+pub fn zero_copy_plans(bank: &Bank, key: &Pubkey) -> Vec<vesting::YearlyPlan> {
+    let data = &bank.account(key).unwrap().data;
+    bytemuck::pod_read_unaligned::<vesting::ZeroCopyPlanChunk>(&data[8..]).to_plans()
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use common::*;
+use vesting::{accounts, instruction, ScheduleValidation, VestingAccount, VestingError, VestingPlanChunk, YearlyPlan};
+
+fn chunk_space(plan_count: usize) -> usize {
+    8 + 32 + 4 + plan_count * (8 + 8 + 1 + 8)
+}
+
+// A vesting whose first page already exists, so appends only resize it
+fn setup_with_page(plans: Vec<YearlyPlan>) -> (Setup, Pubkey) {
+    let mut s = setup();
+    let mut vesting_account: VestingAccount = s.bank.get(&s.vesting_account);
+    vesting_account.plan_chunk_count = 1;
+    s.bank.set_account(s.vesting_account, &vesting_account, 0);
+    let page = set_plan_page(&mut s.bank, &s.vesting_account, 0, plans);
+    (s, page)
+}
+
+fn append(s: &mut Setup, plans: Vec<YearlyPlan>) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::AppendYearlyPlan {
+        vesting_account: s.vesting_account,
+        plan_chunk: plan_page(&s.vesting_account, 0),
+        zero_copy_plans: pda(&[b"zc_plans", s.vesting_account.as_ref()]),
+        parent_plan_chunk: None,
+        parent_vesting_account: None,
+        allocation: None,
+        admin: s.admin,
+        admin_config: s.admin_config,
+        roles: None,
+        plan_timelock: pda(&[b"plan_timelock"]),
+        system_program: system_program::ID,
+    };
+    let args = instruction::AppendYearlyPlan { chunk_index: 0, plans, validation: ScheduleValidation::Incremental };
+    s.bank.process(accounts, &[], args)
+}
+
+fn update(s: &mut Setup, plans: Vec<YearlyPlan>) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::UpdatePlanChunk {
+        vesting_account: s.vesting_account,
+        plan_chunk: plan_page(&s.vesting_account, 0),
+        admin: s.admin,
+        admin_config: s.admin_config,
+        roles: None,
+        plan_timelock: pda(&[b"plan_timelock"]),
+        system_program: system_program::ID,
+        approval_config: pda(&[b"approvals"]),
+        proposal: None,
+    };
+    let args = instruction::UpdatePlanChunk {
+        chunk_index: 0,
+        plans,
+        validation: ScheduleValidation::Incremental,
+        allow_amount_change: true,
+    };
+    s.bank.process(accounts, &[], args)
+}
+
+#[test]
+fn append_grows_the_page_to_its_plans() {
+    let (mut s, page) = setup_with_page(Vec::new());
+    s.bank.set_lamports(&page, rent_exempt(chunk_space(8))); // Prepaid, so no top-up transfer is needed
+
+    append(&mut s, vec![plan(2_000, 100), plan(3_000, 100)]).unwrap();
+    assert_eq!(s.bank.data_len(&page), chunk_space(2));
+    append(&mut s, vec![plan(4_000, 100)]).unwrap();
+    assert_eq!(s.bank.data_len(&page), chunk_space(3));
+
+    let chunk: VestingPlanChunk = s.bank.get(&page);
+    assert_eq!(chunk.plans.iter().map(|p| p.release_time).collect::<Vec<_>>(), vec![2_000, 3_000, 4_000]);
+    assert_eq!(s.bank.get::<VestingAccount>(&s.vesting_account).plan_chunk_count, 1); // Same page
+}
+
+#[test]
+fn page_capacity_is_enforced_on_append() {
+    let (mut s, page) = setup_with_page((0..128).map(|i| plan(2_000 + i, 1)).collect());
+    assert_eq!(
+        append(&mut s, vec![plan(5_000, 1)]),
+        Err(vesting_error(VestingError::InsufficientSpace))     // Open the next page instead
+    );
+    assert_eq!(s.bank.data_len(&page), chunk_space(128));
+}
+
+#[test]
+fn update_shrinks_the_page_and_refunds_rent() {
+    let (mut s, page) = setup_with_page(vec![plan(2_000, 100), plan(3_000, 100), plan(4_000, 100)]);
+
+    let admin_before = s.bank.lamports(&s.admin);
+    update(&mut s, vec![plan(2_000, 300)]).unwrap();
+    assert_eq!(s.bank.data_len(&page), chunk_space(1));
+    assert_eq!(s.bank.lamports(&page), rent_exempt(chunk_space(1)));
+    assert_eq!(s.bank.lamports(&s.admin) - admin_before, rent_exempt(chunk_space(3)) - rent_exempt(chunk_space(1)));
+    assert!(s.bank.get::<VestingPlanChunk>(&page).plans == vec![plan(2_000, 300)]);
+}
+
+#[test]
+fn update_grows_the_page() {
+    let (mut s, page) = setup_with_page(vec![plan(2_000, 100)]);
+    s.bank.set_lamports(&page, rent_exempt(chunk_space(4)));
+
+    update(&mut s, vec![plan(2_000, 25), plan(3_000, 25), plan(4_000, 25), plan(5_000, 25)]).unwrap();
+    assert_eq!(s.bank.data_len(&page), chunk_space(4));
+    assert_eq!(s.bank.get::<VestingPlanChunk>(&page).plans.len(), 4);
+}
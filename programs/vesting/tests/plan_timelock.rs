@@ -0,0 +1,173 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use common::*;
+use vesting::{
+    accounts, instruction, PendingPlanChange, PlanAllocation, PlanChange, PlanPatch, PlanTimelock, ScheduleValidation, VestingAccount,
+    VestingError, VestingPlanChunk, YearlyPlan,
+};
+
+const PLAN_TIMELOCK_SPACE: usize = 8 + 8 + 8 + 8;
+
+fn chunk_space(plan_count: usize) -> usize {
+    8 + 32 + 4 + plan_count * (8 + 8 + 1 + 8)
+}
+
+// One-hour notice on plan changes, and a vesting already running with one page
+fn setup_timelocked(plans: Vec<YearlyPlan>) -> (Setup, Pubkey) {
+    let mut s = setup();
+    let timelock = PlanTimelock { delay: 3_600, pending_delay: 0, pending_delay_at: 0 };
+    s.bank.set_account(pda(&[b"plan_timelock"]), &timelock, PLAN_TIMELOCK_SPACE);
+    let mut vesting_account: VestingAccount = s.bank.get(&s.vesting_account);
+    vesting_account.plan_chunk_count = 1;
+    s.bank.set_account(s.vesting_account, &vesting_account, 0);
+    let page = set_plan_page(&mut s.bank, &s.vesting_account, 0, plans);
+    s.bank.now = 5_000;                                                // Past start_time
+    (s, page)
+}
+
+fn patch(s: &mut Setup, release_time: i64) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::PatchPlanChunk {
+        vesting_account: s.vesting_account,
+        plan_chunk: plan_page(&s.vesting_account, 0),
+        admin: s.admin,
+        admin_config: s.admin_config,
+        roles: None,
+        plan_timelock: pda(&[b"plan_timelock"]),
+        system_program: system_program::ID,
+        approval_config: pda(&[b"approvals"]),
+        proposal: None,
+    };
+    let args = instruction::PatchPlanChunk {
+        chunk_index: 0,
+        patch: PlanPatch::SetReleaseTime { index: 0, release_time },
+        validation: ScheduleValidation::Incremental,
+    };
+    s.bank.process(accounts, &[], args)
+}
+
+fn set_pending(s: &mut Setup, change: PlanChange, eta: i64) -> (Pubkey, Pubkey) {
+    let proposer = Pubkey::new_unique();
+    s.bank.fund(proposer, 1_000_000_000);
+    let pending_change = pda(&[b"pending_plans", s.vesting_account.as_ref(), &0u16.to_le_bytes()]);
+    let pending = PendingPlanChange { vesting_account: s.vesting_account, chunk_index: 0, proposer, change, eta };
+    s.bank.set_account(pending_change, &pending, 0);
+    (pending_change, proposer)
+}
+
+fn execute(s: &mut Setup, pending_change: Pubkey, proposer: Pubkey) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::ExecutePlanChange {
+        vesting_account: s.vesting_account,
+        pending_change,
+        proposer,
+        plan_chunk: plan_page(&s.vesting_account, 0),
+        zero_copy_plans: zero_copy_chunk(&s.vesting_account),
+        parent_plan_chunk: None,
+        allocation: None,
+        admin: s.admin,
+        admin_config: s.admin_config,
+        roles: None,
+        approval_config: pda(&[b"approvals"]),
+        proposal: None,
+        system_program: system_program::ID,
+    };
+    s.bank.process(accounts, &[], instruction::ExecutePlanChange { chunk_index: 0 })
+}
+
+#[test]
+fn direct_edits_wait_for_the_timelock_once_started() {
+    let (mut s, page) = setup_timelocked(vec![plan(20_000, 1_000)]);
+    assert_eq!(patch(&mut s, 30_000), Err(vesting_error(VestingError::TimelockRequired)));
+
+    s.bank.now = 500;                                                  // Before start_time: still being set up
+    patch(&mut s, 30_000).unwrap();
+    assert_eq!(s.bank.get::<VestingPlanChunk>(&page).plans[0].release_time, 30_000);
+}
+
+#[test]
+fn child_appends_wait_for_the_parent_timelock() {
+    let (mut s, _) = setup_timelocked(Vec::new());
+    let parent = Pubkey::new_unique();
+    s.bank.set_account(parent, &plan_vesting(Pubkey::new_unique()), 0); // Started at 1_000
+    let parent_page = set_plan_page(&mut s.bank, &parent, 0, vec![plan(60_000, 1_000)]);
+    let mut child: VestingAccount = s.bank.get(&s.vesting_account);
+    child.parent_vault = Pubkey::new_unique();
+    child.start_time = 50_000;                                         // Child not started: its own timelock passes
+    s.bank.set_account(s.vesting_account, &child, 0);
+    let allocation = pda(&[b"allocation", s.vesting_account.as_ref()]);
+    let ledger = PlanAllocation { parent_vesting: parent, child_vesting: s.vesting_account, entries: Vec::new() };
+    s.bank.set_account(allocation, &ledger, 0);
+
+    let append = |s: &mut Setup, parent_vesting_account| {
+        let accounts = accounts::AppendYearlyPlan {
+            vesting_account: s.vesting_account,
+            plan_chunk: plan_page(&s.vesting_account, 0),
+            zero_copy_plans: zero_copy_chunk(&s.vesting_account),
+            parent_plan_chunk: Some(parent_page),
+            parent_vesting_account,
+            allocation: Some(allocation),
+            admin: s.admin,
+            admin_config: s.admin_config,
+            roles: None,
+            plan_timelock: pda(&[b"plan_timelock"]),
+            system_program: system_program::ID,
+        };
+        let args = instruction::AppendYearlyPlan {
+            chunk_index: 0,
+            plans: vec![plan(60_000, 100)],
+            validation: ScheduleValidation::Incremental,
+        };
+        s.bank.process(accounts, &[], args)
+    };
+    assert_eq!(append(&mut s, None), Err(vesting_error(VestingError::ParentPlanNotFound)));
+    let stranger = Pubkey::new_unique();
+    s.bank.set_account(stranger, &plan_vesting(Pubkey::new_unique()), 0);
+    assert_eq!(append(&mut s, Some(stranger)), Err(vesting_error(VestingError::PlanChunkMismatch))); // Not the ledger's parent
+    assert_eq!(append(&mut s, Some(parent)), Err(vesting_error(VestingError::TimelockRequired)));
+    assert!(s.bank.get::<VestingPlanChunk>(&parent_page).plans == vec![plan(60_000, 1_000)]);
+}
+
+#[test]
+fn staged_appends_run_after_their_eta() {
+    let (mut s, page) = setup_timelocked(vec![plan(20_000, 500)]);
+    s.bank.set_lamports(&page, rent_exempt(chunk_space(2)));           // Prepaid, so no top-up transfer is needed
+    let change = PlanChange::Append { plans: vec![plan(30_000, 500)], validation: ScheduleValidation::Incremental };
+    let (pending_change, proposer) = set_pending(&mut s, change, 8_600);
+
+    assert_eq!(execute(&mut s, pending_change, proposer), Err(vesting_error(VestingError::TimelockNotExpired)));
+    s.bank.now = 8_600;
+    let (proposer_before, pending_rent) = (s.bank.lamports(&proposer), s.bank.lamports(&pending_change));
+    execute(&mut s, pending_change, proposer).unwrap();
+
+    assert!(s.bank.get::<VestingPlanChunk>(&page).plans == vec![plan(20_000, 500), plan(30_000, 500)]);
+    assert_eq!(s.bank.data_len(&page), chunk_space(2));                // Existing page grew in place
+    assert!(!s.bank.exists(&pending_change));
+    assert_eq!(s.bank.lamports(&proposer), proposer_before + pending_rent);
+}
+
+#[test]
+fn staged_changes_can_be_cancelled() {
+    let (mut s, page) = setup_timelocked(vec![plan(20_000, 1_000)]);
+    let change = PlanChange::Patch {
+        patch: PlanPatch::SetReleaseTime { index: 0, release_time: 30_000 },
+        validation: ScheduleValidation::Incremental,
+    };
+    let (pending_change, proposer) = set_pending(&mut s, change, 8_600);
+    let (proposer_before, pending_rent) = (s.bank.lamports(&proposer), s.bank.lamports(&pending_change));
+
+    let accounts = accounts::CancelPlanChange {
+        vesting_account: s.vesting_account,
+        pending_change,
+        proposer,
+        admin: s.admin,
+        admin_config: s.admin_config,
+        roles: None,
+    };
+    s.bank.process(accounts, &[], instruction::CancelPlanChange { chunk_index: 0 }).unwrap();
+    assert!(!s.bank.exists(&pending_change));
+    assert_eq!(s.bank.lamports(&proposer), proposer_before + pending_rent);
+    s.bank.now = 8_600;
+    assert_eq!(execute(&mut s, pending_change, proposer), Err(anchor_error(ErrorCode::AccountNotInitialized)));
+    assert_eq!(s.bank.get::<VestingPlanChunk>(&page).plans[0].release_time, 20_000);
+}
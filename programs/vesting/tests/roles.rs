@@ -0,0 +1,92 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use common::*;
+use vesting::{accounts, instruction, PlanPatch, Role, Roles, ScheduleValidation, VestingAccount, VestingError, VestingPlanChunk};
+
+const ROLES_SPACE: usize = 8 + 5 * (4 + 4 * 32);
+
+fn set_roles(s: &mut Setup, plan_editors: Vec<Pubkey>, pausers: Vec<Pubkey>) -> Pubkey {
+    let roles = pda(&[b"roles"]);
+    let members = Roles {
+        schedulers: Vec::new(),
+        plan_editors,
+        pausers,
+        closers: Vec::new(),
+        token_registrars: Vec::new(),
+    };
+    s.bank.set_account(roles, &members, ROLES_SPACE);
+    roles
+}
+
+fn patch_as(s: &mut Setup, signer: Pubkey, roles: Option<Pubkey>, release_time: i64) -> std::result::Result<(), ProgramError> {
+    s.bank.fund(signer, 1_000_000_000);
+    let accounts = accounts::PatchPlanChunk {
+        vesting_account: s.vesting_account,
+        plan_chunk: plan_page(&s.vesting_account, 0),
+        admin: signer,
+        admin_config: s.admin_config,
+        roles,
+        plan_timelock: pda(&[b"plan_timelock"]),
+        system_program: system_program::ID,
+        approval_config: pda(&[b"approvals"]),
+        proposal: None,
+    };
+    let args = instruction::PatchPlanChunk {
+        chunk_index: 0,
+        patch: PlanPatch::SetReleaseTime { index: 0, release_time },
+        validation: ScheduleValidation::Incremental,
+    };
+    s.bank.process(accounts, &[], args)
+}
+
+fn set_members(s: &mut Setup, signer: Pubkey, role: Role, members: Vec<Pubkey>) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::SetRoleMembers {
+        admin: signer,
+        admin_config: s.admin_config,
+        roles: pda(&[b"roles"]),
+        approval_config: pda(&[b"approvals"]),
+        proposal: None,
+        system_program: system_program::ID,
+    };
+    s.bank.process(accounts, &[], instruction::SetRoleMembers { role, members })
+}
+
+#[test]
+fn roles_grant_only_their_own_instructions() {
+    let mut s = setup();
+    let mut vesting_account: VestingAccount = s.bank.get(&s.vesting_account);
+    vesting_account.plan_chunk_count = 1;
+    s.bank.set_account(s.vesting_account, &vesting_account, 0);
+    let page = set_plan_page(&mut s.bank, &s.vesting_account, 0, vec![plan(2_000, 1_000)]);
+    let (editor, pauser) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let roles = set_roles(&mut s, vec![editor], vec![pauser]);
+
+    assert_eq!(patch_as(&mut s, pauser, Some(roles), 2_500), Err(vesting_error(VestingError::Unauthorized)));
+    assert_eq!(patch_as(&mut s, editor, None, 2_500), Err(vesting_error(VestingError::Unauthorized))); // Membership needs the roles account
+    patch_as(&mut s, editor, Some(roles), 2_500).unwrap();
+    let admin = s.admin;
+    patch_as(&mut s, admin, None, 3_000).unwrap();                 // The admin holds every role
+    assert_eq!(s.bank.get::<VestingPlanChunk>(&page).plans[0].release_time, 3_000);
+}
+
+#[test]
+fn only_the_admin_sets_role_members() {
+    let mut s = setup();
+    let editor = Pubkey::new_unique();
+    let roles = set_roles(&mut s, Vec::new(), Vec::new());
+    let admin = s.admin;
+
+    set_members(&mut s, admin, Role::PlanEditor, vec![editor]).unwrap();
+    assert!(s.bank.get::<Roles>(&roles).plan_editors == vec![editor]);
+    s.bank.fund(editor, 1_000_000_000);
+    assert_eq!(
+        set_members(&mut s, editor, Role::PlanEditor, vec![editor, Pubkey::new_unique()]),
+        Err(vesting_error(VestingError::Unauthorized))             // Members cannot grow their own role
+    );
+    assert_eq!(
+        set_members(&mut s, admin, Role::Pauser, (0..5).map(|_| Pubkey::new_unique()).collect()),
+        Err(vesting_error(VestingError::InvalidParameters))
+    );
+}
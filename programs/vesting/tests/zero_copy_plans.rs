@@ -0,0 +1,81 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use common::*;
+use vesting::{accounts, instruction, AllocationEntry, PlanAllocation, ScheduleValidation, VestingAccount, VestingError};
+
+fn plan_balance(s: &mut Setup, storage: &[Pubkey], vesting_time: i64) -> std::result::Result<(), ProgramError> {
+    let accounts = accounts::GetPlanBalance { vesting_account: s.vesting_account };
+    let remaining: Vec<_> = storage.iter().copied().map(readonly).collect();
+    s.bank.process(accounts, &remaining, instruction::GetPlanBalance { vesting_time })
+}
+
+#[test]
+fn plan_balance_reads_zero_copy_plans() {
+    let mut s = setup();
+    let chunk = set_zero_copy_plans(&mut s.bank, &s.vesting_account, &[plan(2_000, 400), plan(3_000, 600)]);
+
+    plan_balance(&mut s, &[chunk], 3_000).unwrap();
+    assert_eq!(plan_balance(&mut s, &[chunk], 4_000), Err(vesting_error(VestingError::InvalidParameters)));
+    let other = set_zero_copy_plans(&mut s.bank, &Pubkey::new_unique(), &[plan(3_000, 600)]);
+    assert_eq!(plan_balance(&mut s, &[other], 3_000), Err(vesting_error(VestingError::PlanChunkMismatch))); // Another vesting's chunk
+}
+
+#[test]
+fn plan_balance_reads_every_page() {
+    let mut s = setup();
+    let mut vesting_account: VestingAccount = s.bank.get(&s.vesting_account);
+    vesting_account.plan_chunk_count = 2;
+    s.bank.set_account(s.vesting_account, &vesting_account, 0);
+    let first = set_plan_page(&mut s.bank, &s.vesting_account, 0, vec![plan(2_000, 400)]);
+    let second = set_plan_page(&mut s.bank, &s.vesting_account, 1, vec![plan(3_000, 600)]);
+
+    plan_balance(&mut s, &[first, second], 3_000).unwrap();
+    assert_eq!(plan_balance(&mut s, &[first], 3_000), Err(vesting_error(VestingError::PlanChunkMismatch))); // Page missing
+}
+
+#[test]
+fn children_are_funded_from_a_zero_copy_parent() {
+    let mut s = setup();
+    let parent = Pubkey::new_unique();
+    s.bank.set_account(parent, &plan_vesting(Pubkey::new_unique()), 0);
+    let parent_chunk = set_zero_copy_plans(&mut s.bank, &parent, &[plan(2_000, 700), plan(3_000, 300)]);
+
+    let mut child: VestingAccount = s.bank.get(&s.vesting_account);
+    child.parent_vault = Pubkey::new_unique();
+    child.plan_chunk_count = 1;
+    s.bank.set_account(s.vesting_account, &child, 0);
+    let page = set_plan_page(&mut s.bank, &s.vesting_account, 0, Vec::new());
+    s.bank.set_lamports(&page, rent_exempt(8 + 32 + 4 + 25));         // Prepaid, so no top-up transfer is needed
+    let allocation = pda(&[b"allocation", s.vesting_account.as_ref()]);
+    let ledger = PlanAllocation { parent_vesting: parent, child_vesting: s.vesting_account, entries: Vec::new() };
+    s.bank.set_account(allocation, &ledger, 0);
+    s.bank.set_lamports(&allocation, rent_exempt(8 + 32 + 32 + 4 + 48));
+
+    let accounts = accounts::AppendYearlyPlan {
+        vesting_account: s.vesting_account,
+        plan_chunk: page,
+        zero_copy_plans: zero_copy_chunk(&s.vesting_account),
+        parent_plan_chunk: Some(parent_chunk),
+        parent_vesting_account: Some(parent),
+        allocation: Some(allocation),
+        admin: s.admin,
+        admin_config: s.admin_config,
+        roles: None,
+        plan_timelock: pda(&[b"plan_timelock"]),
+        system_program: system_program::ID,
+    };
+    let args = instruction::AppendYearlyPlan {
+        chunk_index: 0,
+        plans: vec![plan(2_000, 200)],
+        validation: ScheduleValidation::Incremental,
+    };
+    s.bank.process(accounts, &[], args).unwrap();
+
+    assert!(zero_copy_plans(&s.bank, &parent_chunk) == vec![plan(2_000, 500), plan(3_000, 300)]);
+    let entries = s.bank.get::<PlanAllocation>(&allocation).entries;
+    assert_eq!(entries.len(), 1);
+    let AllocationEntry { release_time, amount, parent_plan_chunk } = entries[0];
+    assert_eq!((release_time, amount, parent_plan_chunk), (2_000, 200, parent_chunk)); // Ledger names the chunk
+}
@@ -33,6 +33,12 @@ describe("vesting", () => {
   let vestingAccount: PublicKey;
   let planChunk: PublicKey;
 
+  // ProgramData of the vesting program; its upgrade authority is the provider wallet
+  const [programData] = PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
+
   const vestingId = new anchor.BN(1);
   const totalAmount = new anchor.BN(1000);
   const releasedAmount = new anchor.BN(0);
@@ -121,9 +127,36 @@ describe("vesting", () => {
       program.programId
     );
     [planChunk] = await PublicKey.findProgramAddress(
-      [Buffer.from("plans"), vestingAccount.toBuffer()],
+      [Buffer.from("plans"), vestingAccount.toBuffer(), Buffer.from([0, 0])], // page 0 (u16 LE)
+      program.programId
+    );
+  });
+
+  it("Rejects a deployer that is not the upgrade authority", async () => {
+    const [deployAdmin] = await PublicKey.findProgramAddress(
+      [Buffer.from("deploy_admin")],
       program.programId
     );
+    if (await provider.connection.getAccountInfo(deployAdmin)) {
+      return; // Registered by an earlier test file
+    }
+
+    try {
+      await program.methods
+        .initializeDeployer()
+        .accounts({
+          deployer: admin.publicKey,
+          program: program.programId,
+          programData,
+          deployAdmin,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      assert.fail("initializeDeployer should have failed");
+    } catch (err) {
+      assert.equal(err.error?.errorCode?.code, "NotDeployAdmin");
+    }
   });
 
   it("Initializes the deployer", async () => {
@@ -131,15 +164,19 @@ describe("vesting", () => {
       [Buffer.from("deploy_admin")],
       program.programId
     );
+    if (await provider.connection.getAccountInfo(deployAdmin)) {
+      return; // Registered by an earlier test file
+    }
 
     await program.methods
       .initializeDeployer()
       .accounts({
-        deployer: admin.publicKey,
+        deployer: provider.wallet.publicKey, // Upgrade authority of the deployed program
+        program: program.programId,
+        programData,
         deployAdmin,
         systemProgram: SystemProgram.programId,
       })
-      .signers([admin])
       .rpc();
   });
 
@@ -156,7 +193,7 @@ describe("vesting", () => {
     await program.methods
       .initialize()
       .accounts({
-        deployer: admin.publicKey,
+        deployer: provider.wallet.publicKey,
         deployAdmin,
         admin: admin.publicKey,
         adminConfig,
@@ -216,6 +253,8 @@ describe("vesting", () => {
         startTime,
         endTime,
         category,
+        kind: { plan: {} },
+        cliffTime: new anchor.BN(0),
       })
       .accounts({
         admin: admin.publicKey,
@@ -244,6 +283,8 @@ describe("vesting", () => {
       beneficiary.publicKey.toBase58()
     );
     assert.equal(vestingAccountData.totalAmount.toString(), totalAmount.toString());
+    assert.deepEqual(vestingAccountData.kind, { plan: {} });
+    assert.equal(vestingAccountData.cliffTime.toString(), "0");
   });
 
   it("Appends a yearly plan", async () => {
@@ -256,11 +297,12 @@ describe("vesting", () => {
         releaseTime: new anchor.BN(startTime.toNumber() + 1800), // 30 minutes later
         amount: new anchor.BN(500),
         released: false,
+        releasedAmount: new anchor.BN(0),
       },
     ];
 
     await program.methods
-      .appendYearlyPlan(plans)
+      .appendYearlyPlan(0, plans, { incremental: {} })
       .accounts({
         vestingAccount,
         planChunk,
@@ -314,7 +356,16 @@ describe("vesting", () => {
     const vestingTime = new anchor.BN(startTime.toNumber() + 1800);
 
     await program.methods
-      .doVesting(amount, vestingTime, { vestingId })
+      .doVesting(amount, vestingTime, {
+        vestingId,
+        totalAmount,
+        releasedAmount,
+        startTime,
+        endTime,
+        category,
+        kind: { plan: {} },
+        cliffTime: new anchor.BN(0),
+      })
       .accounts({
         admin: admin.publicKey,
         tokenVault: vaultTokenAccount,
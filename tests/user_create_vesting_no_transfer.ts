@@ -33,6 +33,7 @@ describe("vesting", () => {
   let parentPlanChunk: PublicKey;
   let userVestingAccount: PublicKey;
   let userPlanChunk: PublicKey;
+  let parentReleaseTime: anchor.BN;
 
   const parentVestingId = new anchor.BN(1);
   const userVestingId = new anchor.BN(2);
@@ -115,7 +116,7 @@ describe("vesting", () => {
       program.programId
     );
     [parentPlanChunk] = await PublicKey.findProgramAddress(
-      [Buffer.from("plans"), parentVestingAccount.toBuffer()],
+      [Buffer.from("plans"), parentVestingAccount.toBuffer(), Buffer.from([0, 0])], // page 0 (u16 LE)
       program.programId
     );
     [userVestingAccount] = await PublicKey.findProgramAddress(
@@ -128,7 +129,7 @@ describe("vesting", () => {
       program.programId
     );
     [userPlanChunk] = await PublicKey.findProgramAddress(
-      [Buffer.from("plans"), userVestingAccount.toBuffer()],
+      [Buffer.from("plans"), userVestingAccount.toBuffer(), Buffer.from([0, 0])], // page 0 (u16 LE)
       program.programId
     );
   });
@@ -151,15 +152,26 @@ describe("vesting", () => {
       program.programId
     );
 
-    await program.methods
-      .initializeDeployer()
-      .accounts({ deployer: admin.publicKey, deployAdmin, systemProgram: SystemProgram.programId })
-      .signers([admin])
-      .rpc();
+    const [programData] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    );
+    if (!(await provider.connection.getAccountInfo(deployAdmin))) {
+      await program.methods
+        .initializeDeployer()
+        .accounts({
+          deployer: provider.wallet.publicKey, // Upgrade authority of the deployed program
+          program: program.programId,
+          programData,
+          deployAdmin,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
 
     await program.methods
       .initialize()
-      .accounts({ deployer: admin.publicKey, deployAdmin, admin: admin.publicKey, adminConfig, systemProgram: SystemProgram.programId })
+      .accounts({ deployer: provider.wallet.publicKey, deployAdmin, admin: admin.publicKey, adminConfig, systemProgram: SystemProgram.programId })
       .signers([admin])
       .rpc();
 
@@ -193,6 +205,8 @@ describe("vesting", () => {
         startTime: new anchor.BN(Date.now() / 1000),
         endTime: new anchor.BN(Date.now() / 1000 + 3600),
         category,
+        kind: { plan: {} },
+        cliffTime: new anchor.BN(0),
       })
       .accounts({
         admin: admin.publicKey,
@@ -218,16 +232,19 @@ describe("vesting", () => {
           releaseTime: new anchor.BN(Date.now() / 1000 + 1800),
           amount: new anchor.BN(500),
           released: false,
+          releasedAmount: new anchor.BN(0),
         },
         {
             releaseTime: new anchor.BN(Date.now() / 1000 + 3600),
             amount: new anchor.BN(500),
             released: false,
+            releasedAmount: new anchor.BN(0),
         },
       ];
+      parentReleaseTime = plans[0].releaseTime; // The child plan is funded from this tranche
   
       await program.methods
-        .appendYearlyPlan(plans)
+        .appendYearlyPlan(0, plans, { incremental: {} })
         .accounts({
           vestingAccount: parentVestingAccount,
           planChunk: parentPlanChunk,
@@ -263,6 +280,16 @@ describe("vesting", () => {
       program.programId
     );
 
+    const [parentBeneficiaryVault] = await PublicKey.findProgramAddress(
+      [
+        Buffer.from("vault"),
+        admin.publicKey.toBuffer(),
+        mint.toBuffer(),
+        parentVestingId.toBuffer("le", 8),
+      ],
+      program.programId
+    );
+
     const sameAmount = new anchor.BN(500);
 
     await program.methods
@@ -273,6 +300,8 @@ describe("vesting", () => {
         startTime: new anchor.BN(Date.now() / 1000),
         endTime: new anchor.BN(Date.now() / 1000 + 3600),
         category,
+        kind: { plan: {} },
+        cliffTime: new anchor.BN(0),
       })
       .accounts({
         admin: admin.publicKey,
@@ -282,7 +311,7 @@ describe("vesting", () => {
         vestingAccount: userVestingAccount,
         tokenMint: mint,
         tokenVault: vaultTokenAccount,
-        parentVault: vaultTokenAccount,
+        parentVault: parentBeneficiaryVault, // Must be the parent vesting's own vault
         beneficiaryVault,
         vaultAuthority,
         beneficiaryTokenAccount,
@@ -304,6 +333,8 @@ describe("vesting", () => {
     );
     assert.equal(userVestingAccountData.totalAmount.toString(), sameAmount.toString());
     assert.equal(userVestingAccountData.releasedAmount.toString(), sameAmount.toString());
+    assert.deepEqual(userVestingAccountData.kind, { plan: {} });
+    assert.equal(userVestingAccountData.cliffTime.toString(), "0");
 
     const beneficiaryVaultInfo = await getOrCreateAssociatedTokenAccount(
         provider.connection,
@@ -319,20 +350,27 @@ describe("vesting", () => {
         [Buffer.from("admin")],
         program.programId
       );
+      const [allocation] = await PublicKey.findProgramAddress(
+        [Buffer.from("allocation"), userVestingAccount.toBuffer()],
+        program.programId
+      );
       const plans = [
         {
-          releaseTime: new anchor.BN(Date.now() / 1000 + 1800), 
+          releaseTime: parentReleaseTime,
           amount: new anchor.BN(500),
           released: false,
+          releasedAmount: new anchor.BN(0),
         },
       ];
   
       await program.methods
-        .appendYearlyPlan(plans)
+        .appendYearlyPlan(0, plans, { incremental: {} })
         .accounts({
           vestingAccount: userVestingAccount,
           planChunk: userPlanChunk,
           parentPlanChunk: parentPlanChunk,
+          parentVestingAccount, // Its plans are deducted too
+          allocation,
           admin: admin.publicKey,
           adminConfig,
           systemProgram: SystemProgram.programId,
@@ -348,5 +386,11 @@ describe("vesting", () => {
         planChunkData.plans[0].amount.toString(),
         plans[0].amount.toString()
       );
+
+      const allocationData = await program.account.planAllocation.fetch(allocation);
+      assert.equal(allocationData.parentVesting.toBase58(), parentVestingAccount.toBase58());
+      assert.equal(allocationData.entries.length, 1);
+      assert.equal(allocationData.entries[0].amount.toString(), "500");
+      assert.equal(allocationData.entries[0].parentPlanChunk.toBase58(), parentPlanChunk.toBase58());
   });
 });